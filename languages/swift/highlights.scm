@@ -76,6 +76,8 @@
 
 (import_declaration
   "import" @keyword.import)
+(import_declaration
+  (identifier (simple_identifier) @namespace))
 
 (enum_entry
   "case" @keyword)
@@ -95,6 +97,13 @@
 
 (directive) @function.macro
 (diagnostic) @function.macro
+(macro_invocation
+  "#" @function.macro
+  (simple_identifier) @function.macro) ; #expect(...), #Preview { ... }
+
+(attribute
+  "@" @attribute
+  (user_type (type_identifier) @attribute)) ; @resultBuilder, @MainActor, @Test, @testable import
 
 ; Statements
 (for_statement ["for" @keyword.repeat])
@@ -113,8 +122,7 @@
 (if_statement
   "if" @keyword.conditional)
 
-(switch_statement
-  "switch" @keyword.conditional)
+"switch" @keyword.conditional ; also covers `let x = switch value { ... }`
 
 (switch_entry
   "case" @keyword)
@@ -198,6 +206,12 @@
   "++"
   "--"
   "&"
+  "|"
+  "^"
+  "<<"
+  ">>"
+  "&&"
+  "||"
   "~"
   "%="
   "!="
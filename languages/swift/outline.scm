@@ -0,0 +1,109 @@
+; Attributes and modifiers (`@MainActor`, `public`, ...) are deliberately left
+; out of the `@context` captures, so an attributed declaration is shown by its
+; keyword and name rather than by the attribute line.
+;
+; Each `@item` is the whole declaration, which is what go-to-symbol jumps to
+; and what breadcrumbs use to tell which item the cursor is in. The grammar
+; starts a declaration at its leading attributes, though, so the item for
+; `@MainActor\nclass Example {}` starts one row above the `class` line, and so
+; does its sticky header. Capturing the body instead would avoid that, but
+; would also drop the declaration line itself from the item.
+
+(class_declaration
+  declaration_kind: _ @context
+  name: (_) @name) @item
+
+(protocol_declaration
+  "protocol" @context
+  name: (_) @name) @item
+
+; The aliased type is labelled `name` too, hence the anchor.
+(typealias_declaration
+  "typealias" @context
+  .
+  name: (_) @name) @item
+
+; Parameters are included so overloads such as `load(from:)` and `load(url:)`
+; can be told apart. The grammar also labels the return type as `name`, so the
+; name is anchored right after `func` to keep functions from being listed twice.
+(function_declaration
+  "func" @context
+  .
+  name: (_) @name
+  ["(" ")" "," (parameter)]* @context) @item
+
+(protocol_function_declaration
+  "func" @context
+  .
+  name: (_) @name
+  ["(" ")" "," (parameter)]* @context) @item
+
+(init_declaration
+  "init" @name) @item
+
+(deinit_declaration
+  "deinit" @name) @item
+
+; `case south, east` declares several cases in one entry, which are listed
+; together as a single item.
+(enum_entry
+  "case" @context
+  .
+  (simple_identifier) @name
+  ("," @context . (simple_identifier) @name)*) @item
+
+(source_file
+  (property_declaration
+    (value_binding_pattern) @context
+    (pattern (simple_identifier) @name)) @item)
+
+(class_body
+  (property_declaration
+    (value_binding_pattern) @context
+    (pattern (simple_identifier) @name)) @item)
+
+(protocol_property_declaration
+  (pattern (simple_identifier) @name)) @item
+
+; Explicit accessors are listed as children of their property. Stored
+; properties without accessor blocks have nothing to match here.
+(computed_getter
+  (getter_specifier) @name) @item
+
+(computed_setter
+  (setter_specifier) @name) @item
+
+(willset_clause
+  "willSet" @name) @item
+
+(didset_clause
+  "didSet" @name) @item
+
+; `#expect`/`#require` assertions made directly in a `@Test` function body,
+; including `let value = try #require(...)`, are listed under the test, so long
+; tests can be navigated assertion by assertion.
+(function_declaration
+  (modifiers
+    (attribute
+      (user_type
+        (type_identifier) @_test
+        (#eq? @_test "Test"))))
+  body: (function_body
+    (statements
+      [
+        (macro_invocation
+          "#" @context
+          (simple_identifier) @name
+          (#any-of? @name "expect" "require")) @item
+        (try_expression
+          (macro_invocation
+            "#" @context
+            (simple_identifier) @name
+            (#any-of? @name "expect" "require")) @item)
+        (property_declaration
+          value: (try_expression
+            (macro_invocation
+              "#" @context
+              (simple_identifier) @name
+              (#any-of? @name "expect" "require")) @item))
+      ])))
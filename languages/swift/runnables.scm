@@ -0,0 +1,362 @@
+; MARK: Package
+
+; Importing a test framework marks the file as containing tests; the import
+; line carries a runnable for the whole test target. A file can't declare tests
+; without importing one, but queries can neither check that a file declares any
+; tests nor pick one import out of several, so this is a superset: helper files
+; that import XCTest get the runnable too, and a file importing both XCTest and
+; Testing gets one per import. Nimble only provides matchers and is imported
+; alongside Quick or XCTest, so it doesn't count. Only the top-level module
+; counts: `import struct Testing.Test` does, `import MyKit.Testing` doesn't.
+(
+  (source_file
+    (import_declaration
+      (identifier
+        .
+        (simple_identifier) @run
+        (#any-of? @run "XCTest" "Testing" "Quick"))) @_swift-test-all)
+  (#set! tag swift-test-all)
+)
+
+; MARK: Swift Testing
+
+; The `@Suite`/`@Test` attribute patterns below are not anchored inside
+; `modifiers`, so stacked attributes such as `@available(macOS 14, *)` or
+; `@MainActor` may appear before or after them without hiding the test. The
+; same goes for keyword modifiers: `final`, `nonisolated`, `distributed` and
+; access levels all live in the same `modifiers` node, in any combination.
+;
+; A `.bug(...)`/`.issue(...)` trait on a `@Test` function exposes the linked URL
+; or issue identifier, its first argument, as SWIFT_TEST_BUG. The trait is
+; optional, so tests without one match the same patterns. A test with several
+; such traits gets a runnable for each.
+;
+; Conditional compilation (`#if canImport(Testing)`, `#if os(iOS)`, ...) is
+; parsed as `directive` extras rather than wrapping nodes, so declarations in
+; every branch keep their usual parents and match the patterns below.
+
+; @Suite struct/class/actor/enum
+(
+  (class_declaration
+    (modifiers
+      (attribute
+        (user_type
+          (type_identifier) @run
+          (#eq? @run "Suite"))))
+    name: (type_identifier) @SWIFT_TEST_CLASS
+  ) @_swift-testing-suite
+  (#set! tag swift-testing-suite)
+)
+
+; Free @Test functions at the top level of a file
+(source_file
+  (function_declaration
+    (modifiers
+      (attribute
+        (user_type
+          (type_identifier) @run
+          (#eq? @run "Test"))
+        (call_expression
+          (prefix_expression
+            (simple_identifier) @_trait
+            (#any-of? @_trait "bug" "issue"))
+          (call_suffix
+            (value_arguments
+              .
+              (value_argument
+                [
+                  (line_string_literal
+                    (line_str_text) @SWIFT_TEST_BUG)
+                  (integer_literal) @SWIFT_TEST_BUG
+                ]))))?))
+    name: (simple_identifier) @SWIFT_TEST_FUNC
+  ) @_swift-testing-bare-func
+  (#set! tag swift-testing-bare-func)
+)
+
+; @Test functions declared inside a type. The body is left as a wildcard so
+; that enums used as suite namespaces (`enum_class_body`) match as well as
+; structs, classes and actors (`class_body`).
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    body: (_
+      (function_declaration
+        (modifiers
+          (attribute
+            (user_type
+              (type_identifier) @run
+              (#eq? @run "Test"))
+            (call_expression
+              (prefix_expression
+                (simple_identifier) @_trait
+                (#any-of? @_trait "bug" "issue"))
+              (call_suffix
+                (value_arguments
+                  .
+                  (value_argument
+                    [
+                      (line_string_literal
+                        (line_str_text) @SWIFT_TEST_BUG)
+                      (integer_literal) @SWIFT_TEST_BUG
+                    ]))))?))
+        name: (simple_identifier) @SWIFT_TEST_FUNC
+      ) @_swift-testing-member-func))
+  (#set! tag swift-testing-member-func)
+)
+
+; @Test functions declared in an extension, including isolated ones such as
+; `@MainActor extension MySuite`. Whether or not the extended type is marked
+; `@Suite`, SWIFT_TEST_CLASS is the last component of the extended type's name
+; (`extension Outer.Inner` gives `Inner`), which is enough for `--filter` to
+; match the suite.
+(
+  (class_declaration
+    declaration_kind: "extension"
+    name: (user_type
+      (type_identifier) @SWIFT_TEST_CLASS .)
+    body: (class_body
+      (function_declaration
+        (modifiers
+          (attribute
+            (user_type
+              (type_identifier) @run
+              (#eq? @run "Test"))
+            (call_expression
+              (prefix_expression
+                (simple_identifier) @_trait
+                (#any-of? @_trait "bug" "issue"))
+              (call_suffix
+                (value_arguments
+                  .
+                  (value_argument
+                    [
+                      (line_string_literal
+                        (line_str_text) @SWIFT_TEST_BUG)
+                      (integer_literal) @SWIFT_TEST_BUG
+                    ]))))?))
+        name: (simple_identifier) @SWIFT_TEST_FUNC
+      ) @_swift-testing-member-func))
+  (#set! tag swift-testing-member-func)
+)
+
+; MARK: XCTest
+
+; XCTestCase subclasses
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS @run
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#eq? @_superclass "XCTestCase")))
+  ) @_swift-xctest-class
+  (#set! tag swift-xctest-class)
+)
+
+; `test`-prefixed methods of XCTestCase subclasses
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#eq? @_superclass "XCTestCase")))
+    body: (class_body
+      (function_declaration
+        name: (simple_identifier) @SWIFT_TEST_FUNC @run
+        (#match? @SWIFT_TEST_FUNC "^test")
+      ) @_swift-xctest-func))
+  (#set! tag swift-xctest-func)
+)
+
+; Types whose XCTestCase ancestry can't be seen from the declaration, such as
+; extensions or subclasses of a custom base class, can opt in with a
+; `// @XCTestClass` comment on the line above. Only `test`-prefixed methods are
+; tagged.
+(
+  (comment) @_annotation
+  (#match? @_annotation "^//\\s*@XCTestClass\\s*$")
+  .
+  (class_declaration
+    name: [
+      (type_identifier) @SWIFT_TEST_CLASS
+      (user_type
+        (type_identifier) @SWIFT_TEST_CLASS .)
+    ]
+    body: (class_body
+      (function_declaration
+        name: (simple_identifier) @SWIFT_TEST_FUNC @run
+        (#match? @SWIFT_TEST_FUNC "^test")
+      ) @_swift-xctest-func))
+  (#set! tag swift-xctest-func)
+)
+
+; Performance tests: `test`-prefixed methods whose body calls `measure`. Queries
+; can't exclude these from the pattern above, so the runnable is placed on the
+; `measure` call instead of the method name, where it offers to run the test in
+; the release configuration that measurements are meaningful in.
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#eq? @_superclass "XCTestCase")))
+    body: (class_body
+      (function_declaration
+        name: (simple_identifier) @SWIFT_TEST_FUNC
+        (#match? @SWIFT_TEST_FUNC "^test")
+        body: (function_body
+          (statements
+            (call_expression
+              (simple_identifier) @run
+              (#eq? @run "measure"))))
+      ) @_swift-xctest-perf-func))
+  (#set! tag swift-xctest-perf-func)
+)
+
+; `XCTContext.runActivity(named:)` blocks made directly in a test method body,
+; with the activity name as SWIFT_XCTEST_ACTIVITY. The runnable sits on the
+; activity's own line, but XCTest can't run an activity on its own, so its task
+; runs the enclosing test and names the activity in its label.
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#eq? @_superclass "XCTestCase")))
+    body: (class_body
+      (function_declaration
+        name: (simple_identifier) @SWIFT_TEST_FUNC
+        (#match? @SWIFT_TEST_FUNC "^test")
+        body: (function_body
+          (statements
+            (call_expression
+              (navigation_expression
+                (simple_identifier) @_context
+                (#eq? @_context "XCTContext")
+                (navigation_suffix
+                  (simple_identifier) @run
+                  (#eq? @run "runActivity")))
+              (call_suffix
+                (value_arguments
+                  (value_argument
+                    (line_string_literal
+                      (line_str_text) @SWIFT_XCTEST_ACTIVITY))))) @_swift-xctest-activity)))))
+  (#set! tag swift-xctest-activity)
+)
+
+; MARK: Quick
+
+; QuickSpec/AsyncSpec subclasses
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS @run
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#any-of? @_superclass "QuickSpec" "AsyncSpec")))
+  ) @_swift-test-quick-spec
+  (#set! tag swift-test-quick-spec)
+)
+
+; `describe`/`context`/`it` example groups and examples in the spec methods of
+; QuickSpec/AsyncSpec subclasses, with their label as SWIFT_QUICK_EXAMPLE and the
+; spec as SWIFT_TEST_CLASS. Quick derives test names from the whole example
+; path, which `--filter` can't reliably single out, so these run the whole spec.
+; Queries can't require an arbitrarily deep ancestor, so examples are matched up
+; to three levels deep (e.g. `describe` > `context` > `it`); deeper ones are run
+; through an enclosing group or the spec.
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#any-of? @_superclass "QuickSpec" "AsyncSpec")))
+    body: (class_body
+      (function_declaration
+        body: (function_body
+          (statements
+            (call_expression
+              (simple_identifier) @run
+              (#any-of? @run "describe" "context" "it" "fdescribe" "fcontext" "fit")
+              (call_suffix
+                (value_arguments
+                  .
+                  (value_argument
+                    (line_string_literal
+                      (line_str_text) @SWIFT_QUICK_EXAMPLE)))
+                (lambda_literal))) @_swift-quick-example)))))
+  (#set! tag swift-quick-example)
+)
+
+; ... nested in one example group
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#any-of? @_superclass "QuickSpec" "AsyncSpec")))
+    body: (class_body
+      (function_declaration
+        body: (function_body
+          (statements
+            (call_expression
+              (simple_identifier) @_group
+              (#any-of? @_group "describe" "context" "fdescribe" "fcontext")
+              (call_suffix
+                (lambda_literal
+                  (statements
+                    (call_expression
+                      (simple_identifier) @run
+                      (#any-of? @run "describe" "context" "it" "fdescribe" "fcontext" "fit")
+                      (call_suffix
+                        (value_arguments
+                          .
+                          (value_argument
+                            (line_string_literal
+                              (line_str_text) @SWIFT_QUICK_EXAMPLE)))
+                        (lambda_literal))) @_swift-quick-example)))))))))
+  (#set! tag swift-quick-example)
+)
+
+; ... nested in two example groups
+(
+  (class_declaration
+    name: (type_identifier) @SWIFT_TEST_CLASS
+    (inheritance_specifier
+      inherits_from: (user_type
+        (type_identifier) @_superclass
+        (#any-of? @_superclass "QuickSpec" "AsyncSpec")))
+    body: (class_body
+      (function_declaration
+        body: (function_body
+          (statements
+            (call_expression
+              (simple_identifier) @_outer_group
+              (#any-of? @_outer_group "describe" "context" "fdescribe" "fcontext")
+              (call_suffix
+                (lambda_literal
+                  (statements
+                    (call_expression
+                      (simple_identifier) @_group
+                      (#any-of? @_group "describe" "context" "fdescribe" "fcontext")
+                      (call_suffix
+                        (lambda_literal
+                          (statements
+                            (call_expression
+                              (simple_identifier) @run
+                              (#any-of? @run "describe" "context" "it" "fdescribe" "fcontext" "fit")
+                              (call_suffix
+                                (value_arguments
+                                  .
+                                  (value_argument
+                                    (line_string_literal
+                                      (line_str_text) @SWIFT_QUICK_EXAMPLE)))
+                                (lambda_literal))) @_swift-quick-example)))))))))))))
+  (#set! tag swift-quick-example)
+)
@@ -0,0 +1,163 @@
+use crate::query_test::matches;
+
+const HIGHLIGHTS: &str = include_str!("../languages/swift/highlights.scm");
+
+/// Returns the captures of the first node in `source` spanning exactly `text`,
+/// in the order of the patterns that produced them. The last one is the
+/// highlight Zed uses.
+fn captures(source: &str, text: &str) -> Vec<String> {
+    let start = source.find(text).expect("fixture should contain the text");
+    let range = start..start + text.len();
+
+    let mut captures = matches(HIGHLIGHTS, source)
+        .into_iter()
+        .flat_map(|query_match| {
+            let pattern_index = query_match.pattern_index;
+            query_match
+                .captures
+                .into_iter()
+                .filter(|capture| capture.range == range)
+                .map(move |capture| (pattern_index, capture.name))
+        })
+        .collect::<Vec<_>>();
+    captures.sort_by_key(|(pattern_index, _)| *pattern_index);
+    captures.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Returns the highlight Zed gives the first node in `source` spanning
+/// exactly `text`.
+fn highlight(source: &str, text: &str) -> Option<String> {
+    captures(source, text).pop()
+}
+
+#[test]
+fn test_testable_import() {
+    let source = "@testable import App\n";
+
+    assert_eq!(highlight(source, "@").as_deref(), Some("attribute"));
+    assert_eq!(captures(source, "testable"), ["type", "attribute"]);
+    assert_eq!(
+        highlight(source, "import").as_deref(),
+        Some("keyword.import")
+    );
+    assert_eq!(highlight(source, "App").as_deref(), Some("namespace"));
+}
+
+#[test]
+fn test_generic_typealias() {
+    let source = "typealias Handler<T> = (T) -> Void\n";
+
+    assert_eq!(highlight(source, "typealias").as_deref(), Some("keyword"));
+    assert_eq!(highlight(source, "Handler").as_deref(), Some("type"));
+    // Generic parameters are highlighted the same way as on types and
+    // functions.
+    assert_eq!(highlight(source, "T").as_deref(), Some("property"));
+    assert_eq!(
+        highlight("struct Box<T> {}\n", "T").as_deref(),
+        Some("property")
+    );
+}
+
+#[test]
+fn test_existential_typealias() {
+    let source = "typealias Drawable = any Shape\n";
+
+    assert_eq!(highlight(source, "Drawable").as_deref(), Some("type"));
+    assert_eq!(highlight(source, "any").as_deref(), Some("keyword"));
+    assert_eq!(highlight(source, "Shape").as_deref(), Some("type"));
+}
+
+#[test]
+fn test_freestanding_macros() {
+    let source = r#"
+@Test func parses() {
+    #expect(parse("1") == 1)
+}
+
+#Preview {
+    ContentView()
+}
+"#;
+
+    assert_eq!(highlight(source, "#").as_deref(), Some("function.macro"));
+    assert_eq!(
+        highlight(source, "expect").as_deref(),
+        Some("function.macro")
+    );
+    assert_eq!(
+        highlight(source, "Preview").as_deref(),
+        Some("function.macro")
+    );
+}
+
+#[test]
+fn test_result_builder_attribute() {
+    let source = r#"
+@resultBuilder
+struct ListBuilder {}
+"#;
+
+    assert_eq!(highlight(source, "@").as_deref(), Some("attribute"));
+    assert_eq!(
+        highlight(source, "resultBuilder").as_deref(),
+        Some("attribute")
+    );
+    assert_eq!(highlight(source, "ListBuilder").as_deref(), Some("type"));
+}
+
+#[test]
+fn test_custom_operators() {
+    let source = "let value = a <> b |> c\n";
+
+    assert_eq!(highlight(source, "<>").as_deref(), Some("operator"));
+    assert_eq!(highlight(source, "|>").as_deref(), Some("operator"));
+}
+
+#[test]
+fn test_logical_and_bitwise_operators() {
+    let source = r#"
+let bits = a | b ^ c << 1 >> 2
+let flag = x && y || z
+"#;
+
+    for operator in ["|", "^", "<<", ">>", "&&", "||"] {
+        assert_eq!(
+            highlight(source, operator).as_deref(),
+            Some("operator"),
+            "{operator}"
+        );
+    }
+}
+
+#[test]
+fn test_switch_expression() {
+    let source = r#"
+let result = switch x {
+case 1: "one"
+default: "other"
+}
+"#;
+
+    assert_eq!(
+        highlight(source, "switch").as_deref(),
+        Some("keyword.conditional")
+    );
+    assert_eq!(highlight(source, "case").as_deref(), Some("keyword"));
+    assert_eq!(highlight(source, "default").as_deref(), Some("keyword"));
+}
+
+#[test]
+fn test_access_level_with_setter_access() {
+    let source = r#"
+class Counter {
+    public private(set) var count = 0
+}
+"#;
+
+    assert_eq!(highlight(source, "public").as_deref(), Some("keyword"));
+    assert_eq!(
+        highlight(source, "private(set)").as_deref(),
+        Some("keyword")
+    );
+    assert_eq!(highlight(source, "var").as_deref(), Some("keyword"));
+}
@@ -0,0 +1,1756 @@
+use serde::Deserialize;
+use zed_extension_api::{
+    self as zed,
+    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
+    serde_json::{self, Map, Value},
+    settings::LspSettings,
+    CodeLabel, CodeLabelSpan, Result,
+};
+
+/// The resolved command used to start a language server.
+pub struct LanguageServerBinary {
+    pub path: String,
+    pub args: Vec<String>,
+    pub env: zed::EnvVars,
+}
+
+impl LanguageServerBinary {
+    /// The environment variable through which the resolution source is
+    /// exposed, so that it shows up alongside the command when troubleshooting
+    /// which toolchain the server came from.
+    const SOURCE_ENV_VAR: &'static str = "ZED_SOURCEKIT_LSP_SOURCE";
+
+    /// The environment variable listing settings keys the extension doesn't
+    /// know, so that a misspelled setting shows up in the same place.
+    const UNKNOWN_SETTINGS_ENV_VAR: &'static str = "ZED_SOURCEKIT_LSP_UNKNOWN_SETTINGS";
+
+    /// The environment variable listing the settings that `binary.path`
+    /// overrides, so that it's clear which of the conflicting settings won.
+    const IGNORED_SETTINGS_ENV_VAR: &'static str = "ZED_SOURCEKIT_LSP_IGNORED_SETTINGS";
+
+    fn new(source: BinarySource, path: String, args: Vec<String>, mut env: zed::EnvVars) -> Self {
+        set_env(
+            &mut env,
+            Self::SOURCE_ENV_VAR,
+            source.describe().to_string(),
+        );
+        Self { path, args, env }
+    }
+}
+
+/// Where the `sourcekit-lsp` command was resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinarySource {
+    /// `binary.path` in the settings.
+    Settings,
+    /// The `toolchain` setting.
+    Toolchain,
+    /// `swiftly run sourcekit-lsp`.
+    Swiftly,
+    /// `sourcekit-lsp` on the `$PATH`.
+    Path,
+    /// `xcrun sourcekit-lsp`.
+    Xcrun,
+}
+
+impl BinarySource {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Settings => "binary.path",
+            Self::Toolchain => "toolchain",
+            Self::Swiftly => "swiftly",
+            Self::Path => "PATH",
+            Self::Xcrun => "xcrun",
+        }
+    }
+}
+
+/// The extension's own settings, read from `lsp.sourcekit-lsp.settings`.
+///
+/// Known keys are typed so that a value of the wrong type is reported instead
+/// of being ignored. Unknown keys are collected in `extra` rather than
+/// rejected, so settings written for a newer version of the extension still
+/// load; they are listed in `ZED_SOURCEKIT_LSP_UNKNOWN_SETTINGS` instead.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SourceKitLspSettings {
+    /// A toolchain directory or `TOOLCHAINS` identifier to run sourcekit-lsp from.
+    pub toolchain: Option<String>,
+    /// Whether to run sourcekit-lsp through swiftly. Defaults to whether the
+    /// worktree has a `.swift-version` file.
+    pub use_swiftly: Option<bool>,
+    /// Whether to fall back to `xcrun`. Defaults to `true` on macOS only.
+    pub prefer_xcrun: Option<bool>,
+    /// Extra arguments appended to the resolved command.
+    pub arguments: Vec<String>,
+    /// A prebuilt index store, relative to the worktree root unless absolute.
+    pub index_store_path: Option<String>,
+    /// The workspace type to assume when several are detected, passed as
+    /// `--default-workspace-type`. Pinning it skips detection on startup.
+    pub default_workspace_type: Option<WorkspaceType>,
+    /// Whether to enable sourcekit-lsp's background indexing.
+    pub background_indexing: bool,
+    /// Whether to enable sourcekit-lsp's experimental on-type formatting.
+    pub on_type_formatting: bool,
+    /// Any keys not listed above.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl SourceKitLspSettings {
+    fn from_value(settings: Option<Value>) -> Result<Self> {
+        match settings {
+            None | Some(Value::Null) => Ok(Self::default()),
+            Some(settings) => serde_json::from_value(settings)
+                .map_err(|err| format!("invalid `lsp.sourcekit-lsp.settings`: {err}")),
+        }
+    }
+
+    /// Returns the keys that are set and only affect how the command is
+    /// resolved, all of which an explicit `binary.path` overrides.
+    fn command_keys(&self) -> Vec<&'static str> {
+        [
+            ("toolchain", self.toolchain.is_some()),
+            ("use_swiftly", self.use_swiftly.is_some()),
+            ("prefer_xcrun", self.prefer_xcrun.is_some()),
+            ("arguments", !self.arguments.is_empty()),
+            ("index_store_path", self.index_store_path.is_some()),
+            (
+                "default_workspace_type",
+                self.default_workspace_type.is_some(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, set)| set.then_some(key))
+        .collect()
+    }
+
+    /// Returns the initialization options synthesized from the settings, merged
+    /// with the user's own `initialization_options`.
+    fn initialization_options(&self, user_options: Option<Value>) -> Option<Value> {
+        let mut options = Map::new();
+        if self.background_indexing {
+            options.insert("backgroundIndexing".into(), Value::Bool(true));
+        }
+        if self.on_type_formatting {
+            options.insert(
+                "experimentalFeatures".into(),
+                Value::Array(vec![Value::String("on-type-formatting".into())]),
+            );
+        }
+
+        let mut options = Value::Object(options);
+        if let Some(user_options) = user_options {
+            merge_json(&mut options, user_options);
+        }
+        (options != Value::Object(Map::new())).then_some(options)
+    }
+
+    /// Returns the flags derived from the settings, which are appended to the
+    /// resolved command. Relative paths are resolved against `worktree_root`.
+    fn server_args(&self, worktree_root: &str, os: zed::Os) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(workspace_type) = self.default_workspace_type {
+            args.push("--default-workspace-type".into());
+            args.push(workspace_type.as_arg().into());
+        }
+        args.extend(self.arguments.iter().cloned());
+        if let Some(index_store_path) = &self.index_store_path {
+            if index_store_path.trim().is_empty() {
+                return Err("`index_store_path` must be a non-empty path".into());
+            }
+            args.push("-index-store-path".into());
+            args.push(resolve_worktree_path(worktree_root, index_store_path, os));
+        }
+        Ok(args)
+    }
+}
+
+/// A value of sourcekit-lsp's `--default-workspace-type` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum WorkspaceType {
+    #[serde(rename = "swiftPM")]
+    SwiftPm,
+    #[serde(rename = "compilationDatabase")]
+    CompilationDatabase,
+    #[serde(rename = "buildServer")]
+    BuildServer,
+}
+
+impl WorkspaceType {
+    pub fn as_arg(self) -> &'static str {
+        match self {
+            Self::SwiftPm => "swiftPM",
+            Self::CompilationDatabase => "compilationDatabase",
+            Self::BuildServer => "buildServer",
+        }
+    }
+}
+
+pub struct SourceKitLsp;
+
+impl SourceKitLsp {
+    pub const LANGUAGE_SERVER_ID: &'static str = "sourcekit-lsp";
+
+    /// Resolves the `sourcekit-lsp` binary for the given worktree.
+    ///
+    /// An explicit `binary.path` always wins. Otherwise a `toolchain` override
+    /// is honored, then a swiftly-managed toolchain is used when the worktree
+    /// pins one with `.swift-version` (or `use_swiftly` is set), then
+    /// `sourcekit-lsp` on the `$PATH`, and finally `xcrun` (unless
+    /// `prefer_xcrun` is off, which it is by default outside macOS). Flags
+    /// derived from the settings are appended to whichever of these is picked.
+    pub fn language_server_binary(worktree: &zed::Worktree) -> Result<LanguageServerBinary> {
+        let lsp_settings =
+            LspSettings::for_worktree(Self::LANGUAGE_SERVER_ID, worktree).unwrap_or_default();
+        resolve_binary(
+            lsp_settings,
+            worktree.shell_env(),
+            |name| worktree.which(name),
+            worktree.read_text_file(".swift-version").is_ok(),
+            &worktree.root_path(),
+            zed::current_platform().0,
+        )
+    }
+
+    /// Returns the initialization options for the server.
+    ///
+    /// Options synthesized from the extension's settings (such as
+    /// `background_indexing`) are deep-merged with the user's
+    /// `initialization_options`, which take precedence on conflict.
+    pub fn initialization_options(worktree: &zed::Worktree) -> Result<Option<Value>> {
+        let lsp_settings =
+            LspSettings::for_worktree(Self::LANGUAGE_SERVER_ID, worktree).unwrap_or_default();
+        let settings = SourceKitLspSettings::from_value(lsp_settings.settings)?;
+        Ok(settings.initialization_options(lsp_settings.initialization_options))
+    }
+
+    /// Labels completions like the Swift declarations they complete.
+    ///
+    /// `Completion` doesn't expose LSP completion tags, so deprecation is
+    /// detected from a leading `@available(..., deprecated ...)` in the detail
+    /// instead; deprecated completions are rendered with the `comment` highlight.
+    pub fn label_for_completion(mut completion: Completion) -> Option<CodeLabel> {
+        let (deprecated, detail) = split_deprecation(completion.detail.as_deref());
+        completion.detail = detail.map(str::to_string);
+        let label = match completion.kind? {
+            CompletionKind::Class => Some(keyword_label("class", &completion.label)),
+            CompletionKind::Enum => Some(keyword_label("enum", &completion.label)),
+            CompletionKind::Interface => Some(keyword_label("protocol", &completion.label)),
+            CompletionKind::Struct => Some(keyword_label("struct", &completion.label)),
+            CompletionKind::Function | CompletionKind::Method => {
+                let label = strip_placeholders(&completion.label);
+                let code = format!(
+                    "func {label}{}",
+                    signature_suffix(completion.detail.as_deref())
+                );
+                let name_len = label.find('(').unwrap_or(label.len());
+                Some(CodeLabel {
+                    spans: vec![CodeLabelSpan::code_range("func ".len()..code.len())],
+                    filter_range: (0..name_len).into(),
+                    code,
+                })
+            }
+            CompletionKind::Constructor => {
+                // The parameters are highlighted as an `init` signature, but the
+                // type name is displayed in place of `init` and is what's filtered
+                // on, so that typing `Foo` still matches `Foo(bar:)`.
+                let label = strip_placeholders(&completion.label);
+                let (type_name, parameters) =
+                    label.split_at(label.find('(').unwrap_or(label.len()));
+                let parameters = if parameters.is_empty() {
+                    "()"
+                } else {
+                    parameters
+                };
+                let (effects, _) = split_effects(completion.detail.as_deref());
+                let code = format!("init{parameters}{effects}");
+                Some(if type_name.is_empty() {
+                    CodeLabel {
+                        spans: vec![CodeLabelSpan::code_range(0..code.len())],
+                        filter_range: (0.."init".len()).into(),
+                        code,
+                    }
+                } else {
+                    CodeLabel {
+                        spans: vec![
+                            CodeLabelSpan::literal(type_name, Some("type".into())),
+                            CodeLabelSpan::code_range("init".len()..code.len()),
+                        ],
+                        filter_range: (0..type_name.len()).into(),
+                        code,
+                    }
+                })
+            }
+            CompletionKind::EnumMember => {
+                Some(enum_case_label(&strip_placeholders(&completion.label)))
+            }
+            CompletionKind::Operator => {
+                let mut spans = vec![CodeLabelSpan::literal(
+                    completion.label.clone(),
+                    Some("operator".into()),
+                )];
+                push_type_annotation(&mut spans, completion.detail.as_deref());
+                Some(CodeLabel {
+                    code: String::new(),
+                    spans,
+                    filter_range: (0..completion.label.len()).into(),
+                })
+            }
+            CompletionKind::Variable
+            | CompletionKind::Constant
+            | CompletionKind::Value
+            | CompletionKind::Property
+            | CompletionKind::Field => Some(variable_label(
+                &completion.label,
+                completion.detail.as_deref(),
+            )),
+            _ => None,
+        }?;
+        Some(if deprecated {
+            deprecated_label(label)
+        } else {
+            label
+        })
+    }
+
+    /// Labels document and workspace symbols with the Swift keyword that
+    /// declares them:
+    ///
+    /// - `Class`, `Struct`, `Enum` → `class`, `struct`, `enum`
+    /// - `Interface` → `protocol`
+    /// - `TypeParameter` → `typealias`
+    /// - `Namespace`, `Object` → `extension` (SourceKit reports extensions as namespaces)
+    /// - `Method`, `Function` → `func`
+    /// - `Variable`, `Property`, `Field` → `var` (symbols carry no type, so
+    ///   none is shown)
+    /// - `Constant` → `let`
+    /// - `EnumMember` → `case`
+    ///
+    /// SourceKit's symbols don't carry modifiers, but when a symbol name does
+    /// start with an access level (`public`, `private`, ...) it is shown in
+    /// front of the label.
+    pub fn label_for_symbol(symbol: Symbol) -> Option<CodeLabel> {
+        let (access_level, name) = split_access_level(&symbol.name);
+        let label = match symbol.kind {
+            SymbolKind::Class => keyword_label("class", name),
+            SymbolKind::Struct => keyword_label("struct", name),
+            SymbolKind::Enum => keyword_label("enum", name),
+            SymbolKind::Interface => keyword_label("protocol", name),
+            SymbolKind::TypeParameter => keyword_label("typealias", name),
+            SymbolKind::Namespace | SymbolKind::Object => keyword_label("extension", name),
+            SymbolKind::Method | SymbolKind::Function => {
+                let code = format!("func {name}");
+                let name_len = name.find('(').unwrap_or(name.len());
+                CodeLabel {
+                    spans: vec![CodeLabelSpan::code_range("func ".len()..code.len())],
+                    filter_range: (0..name_len).into(),
+                    code,
+                }
+            }
+            SymbolKind::Variable | SymbolKind::Property | SymbolKind::Field => {
+                variable_label(name, None)
+            }
+            SymbolKind::Constant => keyword_label("let", name),
+            SymbolKind::EnumMember => enum_case_label(name),
+            _ => return None,
+        };
+        Some(with_access_level(access_level, label))
+    }
+}
+
+/// Resolves the `sourcekit-lsp` command, as described on
+/// [`SourceKitLsp::language_server_binary`].
+///
+/// Everything it needs from the worktree is passed in, so that resolution can
+/// be tested without one: `which` looks up a command on the worktree's `$PATH`
+/// and `has_swift_version` says whether the worktree has a `.swift-version`
+/// file.
+fn resolve_binary(
+    lsp_settings: LspSettings,
+    mut env: zed::EnvVars,
+    which: impl Fn(&str) -> Option<String>,
+    has_swift_version: bool,
+    worktree_root: &str,
+    os: zed::Os,
+) -> Result<LanguageServerBinary> {
+    let settings = SourceKitLspSettings::from_value(lsp_settings.settings)?;
+    if !settings.extra.is_empty() {
+        let keys = settings.extra.keys().cloned().collect::<Vec<_>>();
+        set_env(
+            &mut env,
+            LanguageServerBinary::UNKNOWN_SETTINGS_ENV_VAR,
+            keys.join(","),
+        );
+    }
+
+    if let Some(binary) = lsp_settings.binary {
+        if let Some(path) = binary.path {
+            let ignored = settings.command_keys();
+            if !ignored.is_empty() {
+                set_env(
+                    &mut env,
+                    LanguageServerBinary::IGNORED_SETTINGS_ENV_VAR,
+                    ignored.join(","),
+                );
+            }
+            return Ok(LanguageServerBinary::new(
+                BinarySource::Settings,
+                path,
+                binary.arguments.unwrap_or_default(),
+                env,
+            ));
+        }
+    }
+
+    let (source, path, mut args) =
+        default_command(&settings, &mut env, which, has_swift_version, os)?;
+    args.extend(settings.server_args(worktree_root, os)?);
+
+    Ok(LanguageServerBinary::new(source, path, args, env))
+}
+
+/// Resolves the command used when no explicit `binary.path` is configured.
+fn default_command(
+    settings: &SourceKitLspSettings,
+    env: &mut zed::EnvVars,
+    which: impl Fn(&str) -> Option<String>,
+    has_swift_version: bool,
+    os: zed::Os,
+) -> Result<(BinarySource, String, Vec<String>)> {
+    if let Some(toolchain) = &settings.toolchain {
+        let (path, args) = apply_toolchain(env, toolchain, which("xcrun"), os)?;
+        return Ok((BinarySource::Toolchain, path, args));
+    }
+
+    let use_swiftly = settings.use_swiftly.unwrap_or(has_swift_version);
+    if use_swiftly {
+        if let Some(path) = which("swiftly") {
+            return Ok((
+                BinarySource::Swiftly,
+                path,
+                vec!["run".into(), "sourcekit-lsp".into()],
+            ));
+        }
+    }
+
+    if let Some(path) = which("sourcekit-lsp") {
+        return Ok((BinarySource::Path, path, Vec::new()));
+    }
+
+    let prefer_xcrun = settings.prefer_xcrun.unwrap_or(os == zed::Os::Mac);
+    if prefer_xcrun {
+        if let Some(path) = which("xcrun") {
+            return Ok((BinarySource::Xcrun, path, vec!["sourcekit-lsp".into()]));
+        }
+    }
+
+    // Only name what was actually looked for, so that a disabled fallback
+    // isn't reported as missing.
+    let searched = match (use_swiftly, prefer_xcrun) {
+        (true, true) => "sourcekit-lsp, swiftly or xcrun",
+        (true, false) => "sourcekit-lsp or swiftly",
+        (false, true) => "sourcekit-lsp or xcrun",
+        (false, false) => "sourcekit-lsp",
+    };
+    let xcrun_note = if prefer_xcrun {
+        ""
+    } else {
+        " (the xcrun fallback is off; set `prefer_xcrun` to `true` to enable it)"
+    };
+    Err(format!(
+        "could not find {searched} on the $PATH{xcrun_note}. Install a Swift toolchain or \
+         point `lsp.sourcekit-lsp.binary.path` at sourcekit-lsp in your settings"
+    ))
+}
+
+/// Splits a leading access-level modifier off `text`.
+fn split_access_level(text: &str) -> (Option<&str>, &str) {
+    const ACCESS_LEVELS: [&str; 6] = [
+        "open",
+        "public",
+        "package",
+        "internal",
+        "fileprivate",
+        "private",
+    ];
+
+    match text.split_once(' ') {
+        Some((word, rest)) if ACCESS_LEVELS.contains(&word) => (Some(word), rest.trim_start()),
+        _ => (None, text),
+    }
+}
+
+/// Prepends an access-level keyword to `label`, keeping the filter range on
+/// the name.
+fn with_access_level(access_level: Option<&str>, mut label: CodeLabel) -> CodeLabel {
+    let Some(access_level) = access_level else {
+        return label;
+    };
+    let prefix = format!("{access_level} ");
+    let offset = prefix.len() as u32;
+    label.spans.insert(
+        0,
+        CodeLabelSpan::literal(prefix, Some("keyword".to_string())),
+    );
+    label.filter_range.start += offset;
+    label.filter_range.end += offset;
+    label
+}
+
+/// Builds a `<keyword> <name>` label that filters on the name alone.
+///
+/// Generic parameters that are part of the name (`Foo<T: Hashable>`) are kept
+/// in the label but left out of the filter range.
+fn keyword_label(keyword: &str, name: &str) -> CodeLabel {
+    let code = format!("{keyword} {name}");
+    let name_start = keyword.len() + 1;
+    let name_len = name.find('<').unwrap_or(name.len());
+    CodeLabel {
+        spans: vec![CodeLabelSpan::code_range(0..code.len())],
+        filter_range: (name_start..name_start + name_len).into(),
+        code,
+    }
+}
+
+/// Builds a `case <name>` label that filters on the name alone.
+///
+/// The case is wrapped in an enum so that associated values
+/// (`success(value: Int)`) parse as a parameter list, highlighting their types.
+fn enum_case_label(name: &str) -> CodeLabel {
+    let prefix = "enum Enum { ";
+    let code = format!("{prefix}case {name} }}");
+    let name_len = name.find('(').unwrap_or(name.len());
+    CodeLabel {
+        spans: vec![CodeLabelSpan::code_range(
+            prefix.len()..prefix.len() + "case ".len() + name.len(),
+        )],
+        filter_range: ("case ".len().."case ".len() + name_len).into(),
+        code,
+    }
+}
+
+/// Builds a `var <name>: <type>` label, omitting the type annotation when
+/// SourceKit doesn't report one.
+fn variable_label(name: &str, detail: Option<&str>) -> CodeLabel {
+    let code = format!("var {name}");
+    let mut spans = vec![CodeLabelSpan::code_range(0..code.len())];
+    push_type_annotation(&mut spans, detail);
+    CodeLabel {
+        spans,
+        filter_range: ("var ".len()..code.len()).into(),
+        code,
+    }
+}
+
+/// Appends `: <detail>` to a label's spans.
+///
+/// The detail is highlighted as a single `type` span, so closure (`(Int) ->
+/// Void`), tuple (`(x: Int, y: Int)`) and generic (`Array<String>`) types read
+/// the same as plain named types.
+fn push_type_annotation(spans: &mut Vec<CodeLabelSpan>, detail: Option<&str>) {
+    let Some(detail) = detail.filter(|detail| !detail.is_empty()) else {
+        return;
+    };
+    spans.push(CodeLabelSpan::literal(": ", Some("punctuation".into())));
+    spans.push(CodeLabelSpan::literal(detail, Some("type".into())));
+}
+
+/// Renders the part of a function signature that follows the parameter list.
+///
+/// SourceKit sometimes folds effects into the completion detail (e.g.
+/// `async throws -> String`), so leading `async`/`throws`/`rethrows` keywords
+/// are moved in front of the arrow instead of being treated as the return type.
+fn signature_suffix(detail: Option<&str>) -> String {
+    let (mut suffix, return_type) = split_effects(detail);
+    if !return_type.is_empty() {
+        suffix.push_str(" -> ");
+        suffix.push_str(return_type);
+    }
+    suffix
+}
+
+/// Splits leading effect keywords off a completion detail, returning them
+/// rendered as ` async throws` alongside whatever follows them.
+fn split_effects(detail: Option<&str>) -> (String, &str) {
+    let mut effects = String::new();
+    let mut rest = detail.unwrap_or_default().trim();
+    loop {
+        let (word, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if !matches!(word, "async" | "throws" | "rethrows") {
+            break;
+        }
+        effects.push(' ');
+        effects.push_str(word);
+        rest = remainder.trim_start();
+    }
+    (effects, rest.trim_start_matches("->").trim_start())
+}
+
+/// Splits a leading `@available(...)` attribute off a completion detail,
+/// reporting whether it marks the completion as deprecated.
+fn split_deprecation(detail: Option<&str>) -> (bool, Option<&str>) {
+    let Some(attribute) = detail.and_then(|detail| detail.trim_start().strip_prefix("@available("))
+    else {
+        return (false, detail);
+    };
+    let mut depth = 1;
+    let Some(end) = attribute.find(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+    }) else {
+        return (false, detail);
+    };
+    let deprecated = attribute[..end]
+        .split(',')
+        .any(|argument| argument.trim().starts_with("deprecated"));
+    let rest = attribute[end + 1..].trim_start();
+    (deprecated, (!rest.is_empty()).then_some(rest))
+}
+
+/// Renders every span of `label` with the `comment` highlight, so deprecated
+/// completions stand out from the rest.
+fn deprecated_label(label: CodeLabel) -> CodeLabel {
+    let spans = label
+        .spans
+        .into_iter()
+        .map(|span| {
+            let text = match span {
+                CodeLabelSpan::CodeRange(range) => {
+                    label.code[range.start as usize..range.end as usize].to_string()
+                }
+                CodeLabelSpan::Literal(literal) => literal.text,
+            };
+            CodeLabelSpan::literal(text, Some("comment".into()))
+        })
+        .collect();
+    CodeLabel { spans, ..label }
+}
+
+/// Replaces SourceKit editor placeholders with their display text, so that
+/// `foo(bar: <#Int#>)` and `foo(bar: <#T##Int##Int#>)` both read `foo(bar: Int)`.
+///
+/// Placeholders are resolved innermost first, which also handles placeholders
+/// nested inside one another. An unterminated `<#` is left as it is.
+fn strip_placeholders(text: &str) -> String {
+    let mut result = text.to_string();
+    while let Some(end) = result.find("#>") {
+        let Some(start) = result[..end].rfind("<#") else {
+            break;
+        };
+        let contents = &result[start + 2..end];
+        let display = match contents.strip_prefix("T##") {
+            Some(typed) => typed.split("##").next().unwrap_or(typed),
+            None => contents,
+        }
+        .to_string();
+        result.replace_range(start..end + 2, &display);
+    }
+    result
+}
+
+/// Resolves a path from the settings against the worktree root, leaving
+/// absolute paths untouched.
+fn resolve_worktree_path(worktree_root: &str, path: &str, os: zed::Os) -> String {
+    if is_absolute_path(path) {
+        path.to_string()
+    } else {
+        format!(
+            "{}{}{path}",
+            worktree_root.trim_end_matches(['/', '\\']),
+            path_separator(os)
+        )
+    }
+}
+
+/// Returns whether `path` is absolute, either as a POSIX path or as a Windows
+/// path with or without a drive letter.
+fn is_absolute_path(path: &str) -> bool {
+    match path.as_bytes() {
+        [b'/' | b'\\', ..] => true,
+        [drive, b':', b'/' | b'\\', ..] => drive.is_ascii_alphabetic(),
+        _ => false,
+    }
+}
+
+/// Returns the separator between the components of a path on `os`.
+fn path_separator(os: zed::Os) -> char {
+    match os {
+        zed::Os::Windows => '\\',
+        zed::Os::Mac | zed::Os::Linux => '/',
+    }
+}
+
+/// Points the environment at the given toolchain and returns the command that
+/// runs its sourcekit-lsp.
+///
+/// A toolchain path (e.g. `/Library/Developer/Toolchains/swift-6.0.xctoolchain`
+/// or `C:\...\Swift\Toolchains\6.0.0+Asserts`) has its `usr/bin` prepended
+/// to `PATH`, and the `sourcekit-lsp` inside it is run directly. Anything else
+/// is treated as a toolchain identifier, which only `xcrun` understands: it is
+/// exported as `TOOLCHAINS` and sourcekit-lsp is run through the given `xcrun`.
+fn apply_toolchain(
+    env: &mut zed::EnvVars,
+    toolchain: &str,
+    xcrun: Option<String>,
+    os: zed::Os,
+) -> Result<(String, Vec<String>)> {
+    if !toolchain.contains(['/', '\\']) {
+        let xcrun = xcrun.ok_or_else(|| {
+            format!(
+                "the toolchain identifier `{toolchain}` needs xcrun, which is not on the $PATH. \
+                 Set `toolchain` to the toolchain's directory instead"
+            )
+        })?;
+        set_env(env, "TOOLCHAINS", toolchain.to_string());
+        return Ok((xcrun, vec!["sourcekit-lsp".into()]));
+    }
+
+    let separator = path_separator(os);
+    let bin_dir = format!(
+        "{}{separator}usr{separator}bin",
+        toolchain.trim_end_matches(['/', '\\'])
+    );
+    let (path_key, path_list_separator, executable) = match os {
+        // Windows environment variable names are case-insensitive, and the
+        // shell environment usually spells it `Path`.
+        zed::Os::Windows => (
+            env.iter()
+                .map(|(key, _)| key.as_str())
+                .find(|key| key.eq_ignore_ascii_case("PATH"))
+                .unwrap_or("PATH")
+                .to_string(),
+            ';',
+            "sourcekit-lsp.exe",
+        ),
+        zed::Os::Mac | zed::Os::Linux => ("PATH".to_string(), ':', "sourcekit-lsp"),
+    };
+    let path = match env.iter().find(|(key, _)| *key == path_key) {
+        Some((_, path)) if !path.is_empty() => format!("{bin_dir}{path_list_separator}{path}"),
+        _ => bin_dir.clone(),
+    };
+    set_env(env, &path_key, path);
+    Ok((format!("{bin_dir}{separator}{executable}"), Vec::new()))
+}
+
+/// Recursively merges `overlay` into `base`.
+///
+/// Objects are merged key by key; anything else in `overlay`, including arrays
+/// and `null`, replaces the value in `base` outright.
+fn merge_json(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn set_env(env: &mut zed::EnvVars, key: &str, value: String) {
+    match env.iter_mut().find(|(existing, _)| existing == key) {
+        Some((_, existing)) => *existing = value,
+        None => env.push((key.to_string(), value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn completion(kind: CompletionKind, label: &str, detail: Option<&str>) -> Completion {
+        Completion {
+            label: label.into(),
+            detail: detail.map(str::to_string),
+            kind: Some(kind),
+            insert_text_format: None,
+        }
+    }
+
+    /// Returns the text a label displays, as the concatenation of its spans.
+    fn display_text(label: &CodeLabel) -> String {
+        label
+            .spans
+            .iter()
+            .map(|span| match span {
+                CodeLabelSpan::CodeRange(range) => {
+                    &label.code[range.start as usize..range.end as usize]
+                }
+                CodeLabelSpan::Literal(literal) => &literal.text,
+            })
+            .collect()
+    }
+
+    /// Returns each span's text along with its highlight, which is `None` for
+    /// code ranges since those are highlighted by the grammar.
+    fn span_highlights(label: &CodeLabel) -> Vec<(String, Option<String>)> {
+        label
+            .spans
+            .iter()
+            .map(|span| match span {
+                CodeLabelSpan::CodeRange(range) => (
+                    label.code[range.start as usize..range.end as usize].to_string(),
+                    None,
+                ),
+                CodeLabelSpan::Literal(literal) => {
+                    (literal.text.clone(), literal.highlight_name.clone())
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the part of a label's displayed text that is matched when filtering.
+    fn filter_text(label: &CodeLabel) -> String {
+        display_text(label)[label.filter_range.start as usize..label.filter_range.end as usize]
+            .to_string()
+    }
+
+    /// Resolves the binary for the given `lsp.sourcekit-lsp` settings in a
+    /// worktree rooted at `/work`, where only the `available` commands are on
+    /// the `$PATH`, each at `/usr/bin/<name>`.
+    fn resolve(
+        lsp_settings: Value,
+        available: &[&str],
+        has_swift_version: bool,
+        os: zed::Os,
+    ) -> Result<LanguageServerBinary> {
+        resolve_binary(
+            serde_json::from_value(lsp_settings).unwrap(),
+            Vec::new(),
+            |name| {
+                available
+                    .contains(&name)
+                    .then(|| format!("/usr/bin/{name}"))
+            },
+            has_swift_version,
+            "/work",
+            os,
+        )
+    }
+
+    fn resolve_err(
+        lsp_settings: Value,
+        available: &[&str],
+        has_swift_version: bool,
+        os: zed::Os,
+    ) -> String {
+        resolve(lsp_settings, available, has_swift_version, os)
+            .err()
+            .expect("resolution should fail")
+    }
+
+    #[test]
+    fn merge_json_merges_nested_objects() {
+        let mut base = json!({
+            "backgroundIndexing": true,
+            "index": { "indexStorePath": "/tmp/index", "prefix": "a" },
+        });
+        merge_json(
+            &mut base,
+            json!({ "index": { "prefix": "b", "enabled": false } }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "backgroundIndexing": true,
+                "index": { "indexStorePath": "/tmp/index", "prefix": "b", "enabled": false },
+            })
+        );
+    }
+
+    #[test]
+    fn merge_json_replaces_arrays() {
+        let mut base = json!({ "experimentalFeatures": ["on-type-formatting"] });
+        merge_json(
+            &mut base,
+            json!({ "experimentalFeatures": ["show-macro-expansions"] }),
+        );
+        assert_eq!(
+            base,
+            json!({ "experimentalFeatures": ["show-macro-expansions"] })
+        );
+    }
+
+    #[test]
+    fn merge_json_replaces_values_with_null() {
+        let mut base = json!({ "backgroundIndexing": true, "index": { "prefix": "a" } });
+        merge_json(
+            &mut base,
+            json!({ "backgroundIndexing": null, "index": null }),
+        );
+        assert_eq!(base, json!({ "backgroundIndexing": null, "index": null }));
+
+        let mut base = json!({ "backgroundIndexing": null });
+        merge_json(&mut base, json!({ "backgroundIndexing": true }));
+        assert_eq!(base, json!({ "backgroundIndexing": true }));
+    }
+
+    #[test]
+    fn strip_placeholders_uses_display_text() {
+        assert_eq!(strip_placeholders("foo(bar: <#Int#>)"), "foo(bar: Int)");
+        assert_eq!(
+            strip_placeholders("foo(bar: <#Int#>, baz: <#String#>)"),
+            "foo(bar: Int, baz: String)"
+        );
+    }
+
+    #[test]
+    fn strip_placeholders_handles_typed_placeholders() {
+        assert_eq!(
+            strip_placeholders("foo(bar: <#T##Int##Int#>)"),
+            "foo(bar: Int)"
+        );
+        assert_eq!(
+            strip_placeholders("map(<#T##transform: (Int) throws -> T##(Int) throws -> T#>)"),
+            "map(transform: (Int) throws -> T)"
+        );
+    }
+
+    #[test]
+    fn strip_placeholders_handles_nested_placeholders() {
+        assert_eq!(
+            strip_placeholders("sink(receiveValue: <#T##(<#Int#>) -> Void##(Int) -> Void#>)"),
+            "sink(receiveValue: (Int) -> Void)"
+        );
+    }
+
+    #[test]
+    fn strip_placeholders_leaves_unterminated_placeholders() {
+        assert_eq!(strip_placeholders("foo(bar: <#abc"), "foo(bar: <#abc");
+        assert_eq!(strip_placeholders("foo(bar: abc#>)"), "foo(bar: abc#>)");
+    }
+
+    #[test]
+    fn function_completion_signatures() {
+        let cases = [
+            (None, "func load()"),
+            (Some("String"), "func load() -> String"),
+            (Some("async"), "func load() async"),
+            (Some("throws -> String"), "func load() throws -> String"),
+            (
+                Some("async throws -> String"),
+                "func load() async throws -> String",
+            ),
+        ];
+        for (detail, code) in cases {
+            let label = SourceKitLsp::label_for_completion(completion(
+                CompletionKind::Function,
+                "load()",
+                detail,
+            ))
+            .unwrap();
+            assert_eq!(label.code, code);
+            assert_eq!(display_text(&label), &code["func ".len()..]);
+            assert_eq!(filter_text(&label), "load");
+        }
+    }
+
+    #[test]
+    fn protocol_typealias_and_extension_symbols() {
+        let cases = [
+            (SymbolKind::Interface, "Loader", "protocol Loader"),
+            (SymbolKind::TypeParameter, "Handler", "typealias Handler"),
+            (SymbolKind::Namespace, "Array", "extension Array"),
+            (SymbolKind::Object, "Array", "extension Array"),
+        ];
+        for (kind, name, code) in cases {
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind,
+                name: name.into(),
+            })
+            .unwrap();
+            assert_eq!(label.code, code);
+            assert_eq!(display_text(&label), code);
+            assert_eq!(filter_text(&label), name);
+        }
+    }
+
+    #[test]
+    fn variable_and_constant_symbols() {
+        let cases = [
+            (SymbolKind::Variable, "var count"),
+            (SymbolKind::Constant, "let count"),
+        ];
+        for (kind, code) in cases {
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind,
+                name: "count".into(),
+            })
+            .unwrap();
+            assert_eq!(label.code, code);
+            assert_eq!(display_text(&label), code);
+            assert_eq!(filter_text(&label), "count");
+        }
+    }
+
+    #[test]
+    fn enum_case_symbols() {
+        let cases = [
+            ("loading", "case loading", "loading"),
+            ("success(value: Int)", "case success(value: Int)", "success"),
+        ];
+        for (name, display, filter) in cases {
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind: SymbolKind::EnumMember,
+                name: name.into(),
+            })
+            .unwrap();
+            assert_eq!(label.code, format!("enum Enum {{ {display} }}"));
+            assert_eq!(display_text(&label), display);
+            assert_eq!(filter_text(&label), filter);
+        }
+    }
+
+    #[test]
+    fn initializer_completions() {
+        let cases = [
+            (
+                "Point(x: <#Int#>, y: <#Int#>)",
+                None,
+                "init(x: Int, y: Int)",
+                "Point(x: Int, y: Int)",
+                "Point",
+            ),
+            ("Point()", None, "init()", "Point()", "Point"),
+            (
+                "Loader(url: <#T##URL##URL#>)",
+                Some("async throws"),
+                "init(url: URL) async throws",
+                "Loader(url: URL) async throws",
+                "Loader",
+            ),
+            ("(x: <#Int#>)", None, "init(x: Int)", "init(x: Int)", "init"),
+        ];
+        for (name, detail, code, display, filter) in cases {
+            let label = SourceKitLsp::label_for_completion(completion(
+                CompletionKind::Constructor,
+                name,
+                detail,
+            ))
+            .unwrap();
+            assert_eq!(label.code, code);
+            assert_eq!(display_text(&label), display);
+            assert_eq!(filter_text(&label), filter);
+        }
+    }
+
+    #[test]
+    fn toolchain_path_is_prepended_to_path() {
+        let mut env = vec![("PATH".to_string(), "/usr/bin:/bin".to_string())];
+        let (path, args) = apply_toolchain(
+            &mut env,
+            "/Library/Developer/Toolchains/swift-6.0.xctoolchain/",
+            Some("/usr/bin/xcrun".into()),
+            zed::Os::Mac,
+        )
+        .unwrap();
+        let bin_dir = "/Library/Developer/Toolchains/swift-6.0.xctoolchain/usr/bin";
+        assert_eq!(path, format!("{bin_dir}/sourcekit-lsp"));
+        assert!(args.is_empty());
+        assert_eq!(
+            env,
+            vec![("PATH".to_string(), format!("{bin_dir}:/usr/bin:/bin"))]
+        );
+
+        let mut env = Vec::new();
+        apply_toolchain(&mut env, "/opt/swift", None, zed::Os::Linux).unwrap();
+        assert_eq!(
+            env,
+            vec![("PATH".to_string(), "/opt/swift/usr/bin".to_string())]
+        );
+    }
+
+    #[test]
+    fn windows_toolchain_path_is_prepended_to_path() {
+        let mut env = vec![(
+            "Path".to_string(),
+            r"C:\Windows\system32;C:\Windows".to_string(),
+        )];
+        let (path, args) = apply_toolchain(
+            &mut env,
+            r"C:\Users\me\AppData\Local\Programs\Swift\Toolchains\6.0.0+Asserts\",
+            None,
+            zed::Os::Windows,
+        )
+        .unwrap();
+        let bin_dir = r"C:\Users\me\AppData\Local\Programs\Swift\Toolchains\6.0.0+Asserts\usr\bin";
+        assert_eq!(path, format!(r"{bin_dir}\sourcekit-lsp.exe"));
+        assert!(args.is_empty());
+        assert_eq!(
+            env,
+            vec![(
+                "Path".to_string(),
+                format!(r"{bin_dir};C:\Windows\system32;C:\Windows")
+            )]
+        );
+
+        let mut env = Vec::new();
+        apply_toolchain(
+            &mut env,
+            "C:/Swift/Toolchains/6.0.0",
+            None,
+            zed::Os::Windows,
+        )
+        .unwrap();
+        assert_eq!(
+            env,
+            vec![(
+                "PATH".to_string(),
+                r"C:/Swift/Toolchains/6.0.0\usr\bin".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn toolchain_identifier_runs_through_xcrun() {
+        let mut env = vec![("PATH".to_string(), "/usr/bin:/bin".to_string())];
+        let (path, args) = apply_toolchain(
+            &mut env,
+            "org.swift.600202407161a",
+            Some("/usr/bin/xcrun".into()),
+            zed::Os::Mac,
+        )
+        .unwrap();
+        assert_eq!(path, "/usr/bin/xcrun");
+        assert_eq!(args, vec!["sourcekit-lsp".to_string()]);
+        assert_eq!(
+            env,
+            vec![
+                ("PATH".to_string(), "/usr/bin:/bin".to_string()),
+                (
+                    "TOOLCHAINS".to_string(),
+                    "org.swift.600202407161a".to_string()
+                ),
+            ]
+        );
+
+        let mut env = Vec::new();
+        assert!(apply_toolchain(&mut env, "org.swift.600202407161a", None, zed::Os::Mac).is_err());
+        assert!(env.is_empty());
+    }
+
+    #[test]
+    fn default_workspace_type_adds_a_flag() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "default_workspace_type": "compilationDatabase",
+            "arguments": ["-Xswiftc", "-DDEBUG"],
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.server_args("/work", zed::Os::Mac).unwrap(),
+            [
+                "--default-workspace-type",
+                "compilationDatabase",
+                "-Xswiftc",
+                "-DDEBUG"
+            ]
+        );
+
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "arguments": ["-Xswiftc", "-DDEBUG"],
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.server_args("/work", zed::Os::Mac).unwrap(),
+            ["-Xswiftc", "-DDEBUG"]
+        );
+    }
+
+    #[test]
+    fn unknown_default_workspace_type_is_rejected() {
+        let err = SourceKitLspSettings::from_value(Some(json!({
+            "default_workspace_type": "swiftpm",
+        })))
+        .unwrap_err();
+        assert!(err.contains("swiftpm"), "{err}");
+    }
+
+    #[test]
+    fn index_store_path_is_resolved_against_the_worktree() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "index_store_path": ".build/index/store",
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.server_args("/work/", zed::Os::Mac).unwrap(),
+            ["-index-store-path", "/work/.build/index/store"]
+        );
+
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "index_store_path": "/var/index/store",
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.server_args("/work", zed::Os::Mac).unwrap(),
+            ["-index-store-path", "/var/index/store"]
+        );
+    }
+
+    #[test]
+    fn windows_index_store_path_is_resolved_against_the_worktree() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "index_store_path": r".build\index\store",
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.server_args(r"C:\work\", zed::Os::Windows).unwrap(),
+            ["-index-store-path", r"C:\work\.build\index\store"]
+        );
+
+        for path in [r"D:\index\store", "D:/index/store", r"\\server\index"] {
+            let settings = SourceKitLspSettings::from_value(Some(json!({
+                "index_store_path": path,
+            })))
+            .unwrap();
+            assert_eq!(
+                settings.server_args(r"C:\work", zed::Os::Windows).unwrap(),
+                ["-index-store-path", path]
+            );
+        }
+    }
+
+    #[test]
+    fn empty_index_store_path_is_rejected() {
+        for path in ["", "  "] {
+            let settings = SourceKitLspSettings::from_value(Some(json!({
+                "index_store_path": path,
+            })))
+            .unwrap();
+            let err = settings.server_args("/work", zed::Os::Mac).unwrap_err();
+            assert!(err.contains("index_store_path"), "{err}");
+        }
+    }
+
+    #[test]
+    fn settings_parse_every_known_key() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "toolchain": "/Library/Developer/Toolchains/swift-latest.xctoolchain",
+            "use_swiftly": false,
+            "prefer_xcrun": true,
+            "arguments": ["-Xswiftc", "-DDEBUG"],
+            "index_store_path": ".build/index/store",
+            "default_workspace_type": "swiftPM",
+            "background_indexing": true,
+            "on_type_formatting": true,
+            "background_indexng": true,
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.toolchain.as_deref(),
+            Some("/Library/Developer/Toolchains/swift-latest.xctoolchain")
+        );
+        assert_eq!(settings.use_swiftly, Some(false));
+        assert_eq!(settings.prefer_xcrun, Some(true));
+        assert_eq!(settings.arguments, ["-Xswiftc", "-DDEBUG"]);
+        assert_eq!(
+            settings.index_store_path.as_deref(),
+            Some(".build/index/store")
+        );
+        assert_eq!(
+            settings.default_workspace_type,
+            Some(WorkspaceType::SwiftPm)
+        );
+        assert!(settings.background_indexing);
+        assert!(settings.on_type_formatting);
+        assert_eq!(
+            Value::Object(settings.extra),
+            json!({ "background_indexng": true })
+        );
+    }
+
+    #[test]
+    fn settings_default_when_unset() {
+        for value in [None, Some(Value::Null), Some(json!({}))] {
+            let settings = SourceKitLspSettings::from_value(value).unwrap();
+            assert_eq!(settings.toolchain, None);
+            assert_eq!(settings.use_swiftly, None);
+            assert_eq!(settings.prefer_xcrun, None);
+            assert!(settings.arguments.is_empty());
+            assert_eq!(settings.index_store_path, None);
+            assert_eq!(settings.default_workspace_type, None);
+            assert!(!settings.background_indexing);
+            assert!(!settings.on_type_formatting);
+            assert!(settings.extra.is_empty());
+        }
+    }
+
+    #[test]
+    fn settings_reject_values_of_the_wrong_type() {
+        let err = SourceKitLspSettings::from_value(Some(json!({ "background_indexing": "yes" })))
+            .unwrap_err();
+        assert!(
+            err.starts_with("invalid `lsp.sourcekit-lsp.settings`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn background_indexing_initialization_option() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "background_indexing": true,
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.initialization_options(None),
+            Some(json!({ "backgroundIndexing": true }))
+        );
+        assert_eq!(
+            settings.initialization_options(Some(json!({ "backgroundIndexing": false }))),
+            Some(json!({ "backgroundIndexing": false }))
+        );
+
+        for value in [None, Some(json!({ "background_indexing": false }))] {
+            let settings = SourceKitLspSettings::from_value(value).unwrap();
+            assert_eq!(settings.initialization_options(None), None);
+            assert_eq!(
+                settings.initialization_options(Some(json!({ "swiftPM": {} }))),
+                Some(json!({ "swiftPM": {} }))
+            );
+        }
+    }
+
+    #[test]
+    fn on_type_formatting_initialization_option() {
+        let settings = SourceKitLspSettings::from_value(Some(json!({
+            "on_type_formatting": true,
+            "background_indexing": true,
+        })))
+        .unwrap();
+        assert_eq!(
+            settings.initialization_options(None),
+            Some(json!({
+                "backgroundIndexing": true,
+                "experimentalFeatures": ["on-type-formatting"],
+            }))
+        );
+
+        for value in [None, Some(json!({ "on_type_formatting": false }))] {
+            let settings = SourceKitLspSettings::from_value(value).unwrap();
+            assert_eq!(settings.initialization_options(None), None);
+        }
+    }
+
+    #[test]
+    fn property_completions() {
+        let label = SourceKitLsp::label_for_completion(completion(
+            CompletionKind::Property,
+            "title",
+            Some("String"),
+        ))
+        .unwrap();
+        assert_eq!(label.code, "var title");
+        assert_eq!(display_text(&label), "var title: String");
+        assert_eq!(filter_text(&label), "title");
+
+        let label =
+            SourceKitLsp::label_for_completion(completion(CompletionKind::Field, "title", None))
+                .unwrap();
+        assert_eq!(display_text(&label), "var title");
+        assert_eq!(filter_text(&label), "title");
+    }
+
+    #[test]
+    fn type_annotations_are_a_single_type_span() {
+        for detail in ["(Int) -> Void", "(x: Int, y: Int)", "Array<String>"] {
+            let mut spans = Vec::new();
+            push_type_annotation(&mut spans, Some(detail));
+            let spans = spans
+                .iter()
+                .map(|span| match span {
+                    CodeLabelSpan::Literal(literal) => {
+                        (literal.text.as_str(), literal.highlight_name.as_deref())
+                    }
+                    CodeLabelSpan::CodeRange(_) => panic!("unexpected code range"),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(spans, [(": ", Some("punctuation")), (detail, Some("type"))]);
+        }
+
+        let mut spans = Vec::new();
+        push_type_annotation(&mut spans, Some(""));
+        push_type_annotation(&mut spans, None);
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn operator_completions() {
+        let label = SourceKitLsp::label_for_completion(completion(
+            CompletionKind::Operator,
+            "+",
+            Some("Int"),
+        ))
+        .unwrap();
+        assert_eq!(
+            span_highlights(&label),
+            [
+                ("+".to_string(), Some("operator".to_string())),
+                (": ".to_string(), Some("punctuation".to_string())),
+                ("Int".to_string(), Some("type".to_string())),
+            ]
+        );
+        assert_eq!(filter_text(&label), "+");
+
+        let label =
+            SourceKitLsp::label_for_completion(completion(CompletionKind::Operator, "<*>", None))
+                .unwrap();
+        assert_eq!(
+            span_highlights(&label),
+            [("<*>".to_string(), Some("operator".to_string()))]
+        );
+        assert_eq!(filter_text(&label), "<*>");
+    }
+
+    #[test]
+    fn generic_parameters_are_not_filtered_on() {
+        let cases = [
+            (
+                SymbolKind::Class,
+                "Cache<Key: Hashable, Value>",
+                "class",
+                "Cache",
+            ),
+            (SymbolKind::Struct, "Box<T>", "struct", "Box"),
+            (SymbolKind::Enum, "Either<L, R>", "enum", "Either"),
+            (SymbolKind::Class, "Cache", "class", "Cache"),
+            (SymbolKind::Struct, "Point", "struct", "Point"),
+            (SymbolKind::Enum, "Direction", "enum", "Direction"),
+        ];
+        for (kind, name, keyword, filter) in cases {
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind,
+                name: name.into(),
+            })
+            .unwrap();
+            assert_eq!(display_text(&label), format!("{keyword} {name}"));
+            assert_eq!(filter_text(&label), filter);
+        }
+    }
+
+    #[test]
+    fn property_and_field_symbols() {
+        for kind in [SymbolKind::Property, SymbolKind::Field] {
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind,
+                name: "title".into(),
+            })
+            .unwrap();
+            assert_eq!(label.code, "var title");
+            assert_eq!(display_text(&label), "var title");
+            assert_eq!(filter_text(&label), "title");
+        }
+    }
+
+    #[test]
+    fn enum_case_completions() {
+        let cases = [
+            ("loading", "case loading"),
+            ("success(<#Data#>)", "case success(Data)"),
+            (
+                "failure(code: <#Int#>, message: <#String#>)",
+                "case failure(code: Int, message: String)",
+            ),
+        ];
+        for (name, display) in cases {
+            let label = SourceKitLsp::label_for_completion(completion(
+                CompletionKind::EnumMember,
+                name,
+                None,
+            ))
+            .unwrap();
+            assert_eq!(label.code, format!("enum Enum {{ {display} }}"));
+            assert_eq!(display_text(&label), display);
+            let name_end = display.find('(').unwrap_or(display.len()) as u32;
+            assert_eq!(label.filter_range.start, "case ".len() as u32);
+            assert_eq!(label.filter_range.end, name_end);
+        }
+    }
+
+    #[test]
+    fn split_deprecation_reads_available_attributes() {
+        assert_eq!(
+            split_deprecation(Some(
+                r#"@available(*, deprecated, message: "Use load(from:)") String"#
+            )),
+            (true, Some("String"))
+        );
+        assert_eq!(
+            split_deprecation(Some("@available(iOS, introduced: 13, deprecated: 16) Int")),
+            (true, Some("Int"))
+        );
+        assert_eq!(
+            split_deprecation(Some("@available(macOS 14, *) String")),
+            (false, Some("String"))
+        );
+        assert_eq!(
+            split_deprecation(Some("@available(*, deprecated)")),
+            (true, None)
+        );
+        assert_eq!(split_deprecation(Some("String")), (false, Some("String")));
+        assert_eq!(
+            split_deprecation(Some("@available(*, deprecated")),
+            (false, Some("@available(*, deprecated"))
+        );
+        assert_eq!(split_deprecation(None), (false, None));
+    }
+
+    #[test]
+    fn deprecated_completions_are_dimmed() {
+        let label = SourceKitLsp::label_for_completion(completion(
+            CompletionKind::Property,
+            "title",
+            Some("@available(*, deprecated) String"),
+        ))
+        .unwrap();
+        assert_eq!(display_text(&label), "var title: String");
+        assert_eq!(filter_text(&label), "title");
+        assert!(span_highlights(&label)
+            .iter()
+            .all(|(_, highlight)| highlight.as_deref() == Some("comment")));
+
+        let label = SourceKitLsp::label_for_completion(completion(
+            CompletionKind::Property,
+            "title",
+            Some("String"),
+        ))
+        .unwrap();
+        assert_eq!(display_text(&label), "var title: String");
+        assert!(span_highlights(&label)
+            .iter()
+            .all(|(_, highlight)| highlight.as_deref() != Some("comment")));
+    }
+
+    #[test]
+    fn access_levels_are_shown_in_symbol_labels() {
+        for access_level in [
+            "open",
+            "public",
+            "package",
+            "internal",
+            "fileprivate",
+            "private",
+        ] {
+            assert_eq!(
+                split_access_level(&format!("{access_level} Loader")),
+                (Some(access_level), "Loader")
+            );
+
+            let label = SourceKitLsp::label_for_symbol(Symbol {
+                kind: SymbolKind::Class,
+                name: format!("{access_level} Loader"),
+            })
+            .unwrap();
+            assert_eq!(display_text(&label), format!("{access_level} class Loader"));
+            assert_eq!(filter_text(&label), "Loader");
+            assert_eq!(
+                span_highlights(&label)[0],
+                (format!("{access_level} "), Some("keyword".to_string()))
+            );
+        }
+
+        assert_eq!(split_access_level("Loader"), (None, "Loader"));
+        assert_eq!(split_access_level("publicist"), (None, "publicist"));
+        assert_eq!(split_access_level("private"), (None, "private"));
+    }
+
+    #[test]
+    fn missing_toolchain_error_names_the_binary_path_setting() {
+        let err = resolve_err(json!({}), &[], false, zed::Os::Mac);
+        assert!(err.contains("`lsp.sourcekit-lsp.binary.path`"), "{err}");
+        assert!(
+            err.contains("could not find sourcekit-lsp or xcrun on the $PATH"),
+            "{err}"
+        );
+
+        let err = resolve_err(json!({}), &[], true, zed::Os::Mac);
+        assert!(
+            err.contains("could not find sourcekit-lsp, swiftly or xcrun on the $PATH"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn missing_toolchain_error_mentions_a_disabled_xcrun_fallback() {
+        // xcrun is installed, but the fallback is off, so it mustn't be
+        // reported as missing.
+        let err = resolve_err(json!({}), &["xcrun"], false, zed::Os::Linux);
+        assert!(
+            err.contains("could not find sourcekit-lsp on the $PATH"),
+            "{err}"
+        );
+        assert!(err.contains("the xcrun fallback is off"), "{err}");
+        assert!(err.contains("`lsp.sourcekit-lsp.binary.path`"), "{err}");
+    }
+
+    #[test]
+    fn xcrun_fallback_defaults_to_on_for_macos() {
+        let binary = resolve(json!({}), &["xcrun"], false, zed::Os::Mac).unwrap();
+        assert_eq!(binary.path, "/usr/bin/xcrun");
+        assert_eq!(binary.args, vec!["sourcekit-lsp".to_string()]);
+    }
+
+    #[test]
+    fn xcrun_fallback_defaults_to_off_for_linux() {
+        let err = resolve_err(json!({}), &["xcrun"], false, zed::Os::Linux);
+        assert!(err.contains("the xcrun fallback is off"), "{err}");
+    }
+
+    #[test]
+    fn prefer_xcrun_overrides_the_platform_default() {
+        let settings = json!({ "settings": { "prefer_xcrun": true } });
+        let binary = resolve(settings, &["xcrun"], false, zed::Os::Linux).unwrap();
+        assert_eq!(binary.path, "/usr/bin/xcrun");
+        assert_eq!(binary.args, vec!["sourcekit-lsp".to_string()]);
+
+        let settings = json!({ "settings": { "prefer_xcrun": false } });
+        let err = resolve_err(settings, &["xcrun"], false, zed::Os::Mac);
+        assert!(err.contains("the xcrun fallback is off"), "{err}");
+    }
+
+    /// Returns the value of `name` in the binary's environment.
+    fn env_var<'a>(binary: &'a LanguageServerBinary, name: &str) -> Option<&'a str> {
+        binary
+            .env
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn resolution_source_is_exposed_for_each_branch() {
+        let everything = ["swiftly", "sourcekit-lsp", "xcrun"];
+        let cases = [
+            (
+                json!({ "binary": { "path": "/opt/sourcekit-lsp" } }),
+                &everything[..],
+                "binary.path",
+            ),
+            (
+                json!({ "settings": { "toolchain": "/opt/swift" } }),
+                &everything[..],
+                "toolchain",
+            ),
+            (
+                json!({ "settings": { "use_swiftly": true } }),
+                &everything[..],
+                "swiftly",
+            ),
+            (json!({}), &everything[..], "PATH"),
+            (json!({}), &["xcrun"][..], "xcrun"),
+        ];
+
+        for (settings, available, source) in cases {
+            let binary = resolve(settings, available, false, zed::Os::Mac).unwrap();
+            assert_eq!(
+                env_var(&binary, LanguageServerBinary::SOURCE_ENV_VAR),
+                Some(source)
+            );
+        }
+    }
+
+    #[test]
+    fn binary_path_wins_over_swiftly() {
+        let settings = json!({
+            "binary": { "path": "/opt/sourcekit-lsp", "arguments": ["--log-level", "debug"] },
+            "settings": { "use_swiftly": true },
+        });
+        let binary = resolve(settings, &["swiftly"], true, zed::Os::Linux).unwrap();
+        assert_eq!(binary.path, "/opt/sourcekit-lsp");
+        assert_eq!(binary.args, vec!["--log-level", "debug"]);
+    }
+
+    #[test]
+    fn swift_version_file_selects_swiftly() {
+        let binary = resolve(
+            json!({}),
+            &["swiftly", "sourcekit-lsp"],
+            true,
+            zed::Os::Linux,
+        )
+        .unwrap();
+        assert_eq!(binary.path, "/usr/bin/swiftly");
+        assert_eq!(binary.args, vec!["run", "sourcekit-lsp"]);
+
+        let settings = json!({ "settings": { "use_swiftly": false } });
+        let binary = resolve(
+            settings,
+            &["swiftly", "sourcekit-lsp"],
+            true,
+            zed::Os::Linux,
+        )
+        .unwrap();
+        assert_eq!(binary.path, "/usr/bin/sourcekit-lsp");
+    }
+
+    #[test]
+    fn settings_overridden_by_binary_path_are_reported() {
+        let settings = json!({
+            "binary": { "path": "/opt/sourcekit-lsp" },
+            "settings": {
+                "toolchain": "/opt/swift",
+                "arguments": ["-Xswiftc", "-DDEBUG"],
+                "background_indexing": true,
+            },
+        });
+        let binary = resolve(settings, &[], false, zed::Os::Mac).unwrap();
+        assert_eq!(binary.path, "/opt/sourcekit-lsp");
+        assert_eq!(
+            env_var(&binary, LanguageServerBinary::IGNORED_SETTINGS_ENV_VAR),
+            Some("toolchain,arguments")
+        );
+    }
+
+    #[test]
+    fn no_settings_are_reported_as_ignored_without_a_conflict() {
+        let settings = json!({
+            "binary": { "path": "/opt/sourcekit-lsp" },
+            "settings": { "background_indexing": true },
+        });
+        let binary = resolve(settings, &[], false, zed::Os::Mac).unwrap();
+        assert_eq!(
+            env_var(&binary, LanguageServerBinary::IGNORED_SETTINGS_ENV_VAR),
+            None
+        );
+
+        let settings = json!({ "settings": { "toolchain": "/opt/swift" } });
+        let binary = resolve(settings, &[], false, zed::Os::Mac).unwrap();
+        assert_eq!(
+            env_var(&binary, LanguageServerBinary::IGNORED_SETTINGS_ENV_VAR),
+            None
+        );
+    }
+
+    #[test]
+    fn settings_are_checked_when_binary_path_is_set() {
+        let settings = json!({
+            "binary": { "path": "/opt/sourcekit-lsp" },
+            "settings": { "toolchian": "/opt/swift" },
+        });
+        let binary = resolve(settings, &[], false, zed::Os::Mac).unwrap();
+        assert_eq!(
+            env_var(&binary, LanguageServerBinary::UNKNOWN_SETTINGS_ENV_VAR),
+            Some("toolchian")
+        );
+
+        let settings = json!({
+            "binary": { "path": "/opt/sourcekit-lsp" },
+            "settings": { "prefer_xcrun": "yes" },
+        });
+        let err = resolve_err(settings, &[], false, zed::Os::Mac);
+        assert!(
+            err.contains("invalid `lsp.sourcekit-lsp.settings`"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::ops::Range;
+
+use crate::query_test::matches;
+
+const OUTLINE: &str = include_str!("../languages/swift/outline.scm");
+
+/// An outline item, as Zed would list it.
+#[derive(Debug)]
+struct Item {
+    depth: usize,
+    text: String,
+    range: Range<usize>,
+    /// The zero-based row the item starts on.
+    row: usize,
+}
+
+/// Returns the outline of `source`, one item per entry in document order.
+///
+/// Like Zed, the `@name` and `@context` captures of each item are joined in
+/// source order, with a space wherever they aren't adjacent, and items are
+/// nested under the items whose range contains theirs.
+fn items(source: &str) -> Vec<Item> {
+    let mut items = matches(OUTLINE, source)
+        .into_iter()
+        .filter_map(|query_match| {
+            let item = query_match.capture("item")?;
+            let mut parts = query_match
+                .captures
+                .iter()
+                .filter(|capture| capture.name == "name" || capture.name == "context")
+                .collect::<Vec<_>>();
+            parts.sort_by_key(|capture| capture.range.start);
+
+            let mut text = String::new();
+            let mut last_end = None;
+            for part in parts {
+                if last_end.is_some_and(|end| part.range.start > end) {
+                    text.push(' ');
+                }
+                text.push_str(&part.text);
+                last_end = Some(part.range.end);
+            }
+
+            Some(Item {
+                depth: 0,
+                text,
+                range: item.range.clone(),
+                row: source[..item.range.start].matches('\n').count(),
+            })
+        })
+        .collect::<Vec<_>>();
+    items.sort_by_key(|item| (item.range.start, std::cmp::Reverse(item.range.end)));
+
+    let mut stack: Vec<Range<usize>> = Vec::new();
+    for item in &mut items {
+        while stack
+            .last()
+            .is_some_and(|parent| parent.end < item.range.end || parent.start > item.range.start)
+        {
+            stack.pop();
+        }
+        item.depth = stack.len();
+        stack.push(item.range.clone());
+    }
+    items
+}
+
+/// Returns the outline of `source` as indented text, one line per item.
+fn outline(source: &str) -> Vec<String> {
+    items(source)
+        .into_iter()
+        .map(|item| format!("{}{}", "  ".repeat(item.depth), item.text))
+        .collect()
+}
+
+#[test]
+fn test_class_with_members() {
+    let source = r#"
+class Cache {
+    var count = 0
+    let name: String
+    init(name: String) { self.name = name }
+    deinit {}
+    func clear() {}
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        [
+            "class Cache",
+            "  var count",
+            "  let name",
+            "  init",
+            "  deinit",
+            "  func clear()",
+        ]
+    );
+}
+
+#[test]
+fn test_struct_actor_and_extension() {
+    let source = r#"
+struct Point {}
+actor Counter {}
+extension Point {
+    func moved() -> Point { self }
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        [
+            "struct Point",
+            "actor Counter",
+            "extension Point",
+            "  func moved()",
+        ]
+    );
+}
+
+#[test]
+fn test_function_with_return_type_is_listed_once() {
+    let source = r#"
+func load() -> Int { 1 }
+func parse(_ text: String) -> [String] { [] }
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["func load()", "func parse(_ text: String)"]
+    );
+}
+
+#[test]
+fn test_function_overloads_include_parameters() {
+    let source = r#"
+func load(from path: String) {}
+func load(url: URL) {}
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["func load(from path: String)", "func load(url: URL)"]
+    );
+}
+
+#[test]
+fn test_operator_function() {
+    let source = r#"
+struct Vector {
+    static func <*> (lhs: Vector, rhs: Vector) -> Vector { lhs }
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["struct Vector", "  func <*> (lhs: Vector, rhs: Vector)",]
+    );
+}
+
+#[test]
+fn test_enum_cases() {
+    let source = r#"
+enum Direction {
+    case north
+    case south, east
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["enum Direction", "  case north", "  case south, east",]
+    );
+}
+
+#[test]
+fn test_protocol_requirements() {
+    let source = r#"
+protocol Store {
+    var count: Int { get }
+    func save(_ value: Int)
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["protocol Store", "  count", "  func save(_ value: Int)",]
+    );
+}
+
+#[test]
+fn test_nested_types() {
+    let source = r#"
+struct Outer {
+    enum Inner {
+        case value
+    }
+    struct Leaf {}
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        [
+            "struct Outer",
+            "  enum Inner",
+            "    case value",
+            "  struct Leaf",
+        ]
+    );
+}
+
+#[test]
+fn test_typealias() {
+    let source = r#"
+typealias Handler = (Int) -> Void
+"#;
+
+    assert_eq!(outline(source), ["typealias Handler"]);
+}
+
+#[test]
+fn test_attributes_and_modifiers_are_not_context() {
+    let source = r#"
+@MainActor
+public final class Model {
+    @Published private(set) var value = 0
+    @discardableResult
+    public func reset() -> Bool { true }
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        ["class Model", "  var value", "  func reset()",]
+    );
+}
+
+#[test]
+fn test_local_variables_are_not_listed() {
+    let source = r#"
+let limit = 10
+func run() {
+    let local = 1
+    print(local)
+}
+"#;
+
+    assert_eq!(outline(source), ["let limit", "func run()"]);
+}
+
+#[test]
+fn test_property_accessors() {
+    let source = r#"
+struct Box {
+    var value: Int {
+        get { 0 }
+        set {}
+    }
+    var observed = 0 {
+        willSet {}
+        didSet {}
+    }
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        [
+            "struct Box",
+            "  var value",
+            "    get",
+            "    set",
+            "  var observed",
+            "    willSet",
+            "    didSet",
+        ]
+    );
+}
+
+/// A declaration starts at its attributes, so an attributed item starts on the
+/// attribute line, one row above its keyword. See the note in `outline.scm`.
+#[test]
+fn test_attributed_type_item_starts_at_its_attributes() {
+    let source = "@MainActor\nclass Example {}\n";
+
+    let items = items(source);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].text, "class Example");
+    assert_eq!(items[0].row, 0);
+    assert_eq!(items[0].range, 0..source.trim_end().len());
+}
+
+#[test]
+fn test_attributed_members_span_their_declarations() {
+    let source = r#"
+@MainActor
+final class Model {
+    @available(*, deprecated)
+    init() {}
+
+    @MainActor
+    func run() {}
+}
+
+@objc
+protocol Runner {
+    init(value: Int)
+    func run()
+}
+"#;
+
+    let items = items(source)
+        .into_iter()
+        .map(|item| (item.depth, item.text, item.row))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        items,
+        [
+            (0, "class Model".to_string(), 1),
+            (1, "init".to_string(), 3),
+            (1, "func run()".to_string(), 6),
+            (0, "protocol Runner".to_string(), 10),
+            (1, "init".to_string(), 12),
+            (1, "func run()".to_string(), 13),
+        ]
+    );
+}
+
+#[test]
+fn test_assertions_in_test_functions() {
+    let source = r#"
+@Test func parsing() throws {
+    let value = try #require(parse("1"))
+    #expect(value == 1)
+    try #require(value > 0)
+}
+
+func helper() throws {
+    let value = try #require(parse("1"))
+    #expect(value == 1)
+}
+"#;
+
+    assert_eq!(
+        outline(source),
+        [
+            "func parsing()",
+            "  #require",
+            "  #expect",
+            "  #require",
+            "func helper()",
+        ]
+    );
+}
+
+#[test]
+fn test_property_with_setter_access() {
+    let source = r#"
+class Counter {
+    public private(set) var count = 0
+}
+"#;
+
+    assert_eq!(outline(source), ["class Counter", "  var count"]);
+}
@@ -0,0 +1,145 @@
+//! Helpers for testing the queries in `languages/swift` against the Swift
+//! grammar pinned in `extension.toml`.
+
+use std::ops::Range;
+
+use tree_sitter::{Language, Parser, Query, QueryCursor, QueryMatch, QueryPredicateArg};
+
+/// Every query file shipped with the extension. Zed drops a query file
+/// entirely if any of its patterns fails to compile.
+const QUERIES: [(&str, &str); 8] = [
+    (
+        "highlights.scm",
+        include_str!("../languages/swift/highlights.scm"),
+    ),
+    (
+        "indents.scm",
+        include_str!("../languages/swift/indents.scm"),
+    ),
+    (
+        "injections.scm",
+        include_str!("../languages/swift/injections.scm"),
+    ),
+    ("locals.scm", include_str!("../languages/swift/locals.scm")),
+    (
+        "outline.scm",
+        include_str!("../languages/swift/outline.scm"),
+    ),
+    (
+        "runnables.scm",
+        include_str!("../languages/swift/runnables.scm"),
+    ),
+    ("tags.scm", include_str!("../languages/swift/tags.scm")),
+    (
+        "textobjects.scm",
+        include_str!("../languages/swift/textobjects.scm"),
+    ),
+];
+
+/// A node captured by a match.
+#[derive(Debug)]
+pub struct Capture {
+    /// The capture name, without the leading `@`.
+    pub name: String,
+    pub text: String,
+    /// The byte range of the captured node.
+    pub range: Range<usize>,
+}
+
+/// A match of one of a query's patterns.
+#[derive(Debug)]
+pub struct Match {
+    /// The index of the pattern in the query. Zed gives later patterns
+    /// precedence when several capture the same node.
+    pub pattern_index: usize,
+    /// The value of the pattern's `#set! tag` property, if any.
+    pub tag: Option<String>,
+    pub captures: Vec<Capture>,
+}
+
+impl Match {
+    /// Returns the first capture with the given name.
+    pub fn capture(&self, name: &str) -> Option<&Capture> {
+        self.captures.iter().find(|capture| capture.name == name)
+    }
+}
+
+fn language() -> Language {
+    tree_sitter_swift::language()
+}
+
+/// Runs `query` over `source`, returning its matches in document order.
+///
+/// Panics if the query doesn't compile or the source doesn't parse cleanly,
+/// so that a broken fixture can't pass by matching nothing.
+pub fn matches(query: &str, source: &str) -> Vec<Match> {
+    let query = Query::new(language(), query).expect("query should compile");
+    let mut parser = Parser::new();
+    parser.set_language(language()).unwrap();
+    let tree = parser.parse(source, None).unwrap();
+    assert!(
+        !tree.root_node().has_error(),
+        "fixture should parse without errors: {}",
+        tree.root_node().to_sexp()
+    );
+
+    let mut cursor = QueryCursor::new();
+    cursor
+        .matches(&query, tree.root_node(), source.as_bytes())
+        .filter(|query_match| satisfies_any_of(&query, query_match, source))
+        .map(|query_match| to_match(&query, &query_match, source))
+        .collect()
+}
+
+fn to_match(query: &Query, query_match: &QueryMatch, source: &str) -> Match {
+    Match {
+        pattern_index: query_match.pattern_index,
+        tag: query
+            .property_settings(query_match.pattern_index)
+            .iter()
+            .find(|property| &*property.key == "tag")
+            .and_then(|property| property.value.as_deref().map(str::to_string)),
+        captures: query_match
+            .captures
+            .iter()
+            .map(|capture| Capture {
+                name: query.capture_names()[capture.index as usize].to_string(),
+                text: source[capture.node.byte_range()].to_string(),
+                range: capture.node.byte_range(),
+            })
+            .collect(),
+    }
+}
+
+/// Applies `#any-of?` predicates, which Zed evaluates but tree-sitter 0.20
+/// hands back as general predicates.
+fn satisfies_any_of(query: &Query, query_match: &QueryMatch, source: &str) -> bool {
+    query
+        .general_predicates(query_match.pattern_index)
+        .iter()
+        .filter(|predicate| &*predicate.operator == "any-of?")
+        .all(|predicate| {
+            let [QueryPredicateArg::Capture(index), values @ ..] = &predicate.args[..] else {
+                return true;
+            };
+            query_match
+                .captures
+                .iter()
+                .filter(|capture| capture.index == *index)
+                .all(|capture| {
+                    let text = &source[capture.node.byte_range()];
+                    values
+                        .iter()
+                        .any(|value| matches!(value, QueryPredicateArg::String(value) if &**value == text))
+                })
+        })
+}
+
+#[test]
+fn every_query_compiles() {
+    for (name, query) in QUERIES {
+        if let Err(err) = Query::new(language(), query) {
+            panic!("{name} doesn't compile: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::query_test::matches;
+
+const RUNNABLES: &str = include_str!("../languages/swift/runnables.scm");
+
+/// A runnable, as Zed would see it.
+#[derive(Debug)]
+struct Runnable {
+    tag: String,
+    /// The text of the `@run` capture, which places the runnable.
+    run: String,
+    /// The captures exposed to tasks as `ZED_CUSTOM_*` variables.
+    env: BTreeMap<String, String>,
+}
+
+/// Returns the runnables in `source`, ordered by where they're placed.
+///
+/// Like Zed, matches without a tag or a `@run` capture are dropped, and
+/// captures whose name starts with `_` aren't exposed to tasks.
+fn runnables(source: &str) -> Vec<Runnable> {
+    let mut matches = matches(RUNNABLES, source);
+    matches.sort_by_key(|query_match| {
+        let run = query_match.capture("run").map(|run| run.range.start);
+        (run, query_match.pattern_index)
+    });
+    matches
+        .into_iter()
+        .filter_map(|query_match| {
+            let run = query_match.capture("run")?.text.clone();
+            let env = query_match
+                .captures
+                .iter()
+                .filter(|capture| capture.name != "run" && !capture.name.starts_with('_'))
+                .map(|capture| (capture.name.clone(), capture.text.clone()))
+                .collect();
+            Some(Runnable {
+                tag: query_match.tag?,
+                run,
+                env,
+            })
+        })
+        .collect()
+}
+
+/// Returns each runnable in `source` as `tag(run) VAR=value ...`, where `run`
+/// is the text the runnable is placed on.
+fn summary(source: &str) -> Vec<String> {
+    runnables(source)
+        .into_iter()
+        .map(|runnable| {
+            let mut line = format!("{}({})", runnable.tag, runnable.run);
+            for (name, value) in runnable.env {
+                line.push_str(&format!(" {name}={value}"));
+            }
+            line
+        })
+        .collect()
+}
+
+// Swift requires declarations sharing a line to be separated by `;`. Without
+// it the grammar wraps all but the last declaration in an error node.
+#[test]
+fn test_tests_on_one_line() {
+    let source = r#"
+@Test func a() {}; @Test func b() {}
+
+struct Suite {
+    @Test func c() {}; @Test func d() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=a",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=b",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Suite SWIFT_TEST_FUNC=c",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Suite SWIFT_TEST_FUNC=d",
+        ]
+    );
+}
+
+#[test]
+fn test_bug_trait_is_exposed() {
+    let source = r#"
+@Test(.bug("https://example.com/issues/1"))
+func linked() {}
+
+struct Suite {
+    @Test(.tags(.slow), .bug(id: 12))
+    func member() {}
+
+    @Test(.tags(.slow))
+    func unlinked() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-bare-func(Test) SWIFT_TEST_BUG=https://example.com/issues/1 SWIFT_TEST_FUNC=linked",
+            "swift-testing-member-func(Test) SWIFT_TEST_BUG=12 SWIFT_TEST_CLASS=Suite SWIFT_TEST_FUNC=member",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Suite SWIFT_TEST_FUNC=unlinked",
+        ]
+    );
+}
+
+#[test]
+fn test_performance_tests_run_from_measure() {
+    let source = r#"
+final class PerformanceTests: XCTestCase {
+    func testParsing() {
+        measure {
+            parse()
+        }
+    }
+
+    func testRegular() {
+        parse()
+    }
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(PerformanceTests) SWIFT_TEST_CLASS=PerformanceTests",
+            "swift-xctest-func(testParsing) SWIFT_TEST_CLASS=PerformanceTests SWIFT_TEST_FUNC=testParsing",
+            "swift-xctest-perf-func(measure) SWIFT_TEST_CLASS=PerformanceTests SWIFT_TEST_FUNC=testParsing",
+            "swift-xctest-func(testRegular) SWIFT_TEST_CLASS=PerformanceTests SWIFT_TEST_FUNC=testRegular",
+        ]
+    );
+}
+
+#[test]
+fn test_xctest_methods_with_effects() {
+    let source = r#"
+class EffectTests: XCTestCase {
+    func testAsync() async {}
+    func testThrows() throws {}
+    func testBoth() async throws {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(EffectTests) SWIFT_TEST_CLASS=EffectTests",
+            "swift-xctest-func(testAsync) SWIFT_TEST_CLASS=EffectTests SWIFT_TEST_FUNC=testAsync",
+            "swift-xctest-func(testThrows) SWIFT_TEST_CLASS=EffectTests SWIFT_TEST_FUNC=testThrows",
+            "swift-xctest-func(testBoth) SWIFT_TEST_CLASS=EffectTests SWIFT_TEST_FUNC=testBoth",
+        ]
+    );
+}
+
+#[test]
+fn test_whole_target_runnable() {
+    let source = r#"
+import Foundation
+import Nimble
+import Quick
+
+final class ParserSpec: QuickSpec {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-test-all(Quick)",
+            "swift-test-quick-spec(ParserSpec) SWIFT_TEST_CLASS=ParserSpec",
+        ]
+    );
+}
+
+#[test]
+fn test_whole_target_runnable_per_framework_import() {
+    // Queries can't pick one import out of several, so a file mixing XCTest
+    // and Swift Testing gets a runnable for each.
+    let source = r#"
+import Testing
+import XCTest
+
+@Test func parses() {}
+final class ParserTests: XCTestCase {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-test-all(Testing)",
+            "swift-test-all(XCTest)",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=parses",
+            "swift-xctest-class(ParserTests) SWIFT_TEST_CLASS=ParserTests",
+        ]
+    );
+}
+
+#[test]
+fn test_no_whole_target_runnable_without_tests() {
+    let source = r#"
+import Foundation
+
+struct Parser {
+    func parse() {}
+}
+"#;
+
+    assert!(summary(source).is_empty());
+}
+
+#[test]
+fn test_member_tests_with_modifiers() {
+    let source = r#"
+struct ModifierTests {
+    @Test mutating func mutates() {}
+    @Test static func isStatic() {}
+    @Test nonisolated func isNonisolated() {}
+    @Test func returnsValue() -> Int { 1 }
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=ModifierTests SWIFT_TEST_FUNC=mutates",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=ModifierTests SWIFT_TEST_FUNC=isStatic",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=ModifierTests SWIFT_TEST_FUNC=isNonisolated",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=ModifierTests SWIFT_TEST_FUNC=returnsValue",
+        ]
+    );
+}
+
+#[test]
+fn test_traits_keep_the_runnable() {
+    let source = r#"
+@Test(.bug("FOO-123")) func foo() {}
+
+@Test("Parses empty input", .bug("FOO-124"), .tags(.parser))
+func parsesEmptyInput() {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-bare-func(Test) SWIFT_TEST_BUG=FOO-123 SWIFT_TEST_FUNC=foo",
+            "swift-testing-bare-func(Test) SWIFT_TEST_BUG=FOO-124 SWIFT_TEST_FUNC=parsesEmptyInput",
+        ]
+    );
+}
+
+#[test]
+fn test_effectful_tests_in_suite_class() {
+    let source = r#"
+@Suite class S {
+    @Test func a() async {}
+    @Test func b() throws {}
+    @Test func c() async throws {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=S",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=S SWIFT_TEST_FUNC=a",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=S SWIFT_TEST_FUNC=b",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=S SWIFT_TEST_FUNC=c",
+        ]
+    );
+}
+
+#[test]
+fn test_suites_without_members() {
+    let source = r#"
+@Suite struct Empty {}
+@Suite struct Conforming: SomeProtocol {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Empty",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Conforming",
+        ]
+    );
+}
+
+#[test]
+fn test_generic_test_types() {
+    let source = r#"
+class GenericTests<T>: XCTestCase {
+    func testValue() {}
+}
+
+class ConstrainedTests<Key: Hashable, Value>: XCTestCase where Value: Codable {}
+
+@Suite struct GenericSuite<T: Equatable> {
+    @Test func equal() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(GenericTests) SWIFT_TEST_CLASS=GenericTests",
+            "swift-xctest-func(testValue) SWIFT_TEST_CLASS=GenericTests SWIFT_TEST_FUNC=testValue",
+            "swift-xctest-class(ConstrainedTests) SWIFT_TEST_CLASS=ConstrainedTests",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=GenericSuite",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=GenericSuite SWIFT_TEST_FUNC=equal",
+        ]
+    );
+}
+
+#[test]
+fn test_skipped_tests_are_regular_tests() {
+    let source = r#"
+class SkippingTests: XCTestCase {
+    func testSkipped() throws {
+        throw XCTSkip("Not supported")
+    }
+
+    func testRegular() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(SkippingTests) SWIFT_TEST_CLASS=SkippingTests",
+            "swift-xctest-func(testSkipped) SWIFT_TEST_CLASS=SkippingTests SWIFT_TEST_FUNC=testSkipped",
+            "swift-xctest-func(testRegular) SWIFT_TEST_CLASS=SkippingTests SWIFT_TEST_FUNC=testRegular",
+        ]
+    );
+}
+
+#[test]
+fn test_commented_out_tests() {
+    let source = r#"
+// @Test func disabled() {}
+// class DisabledTests: XCTestCase {}
+/*
+@Suite struct DisabledSuite {
+    @Test func disabled() {}
+}
+*/
+"#;
+
+    assert!(summary(source).is_empty());
+}
+
+#[test]
+fn test_tests_in_conditional_compilation() {
+    let source = r#"
+#if os(iOS)
+class PlatformTests: XCTestCase {
+    func testPlatform() {}
+}
+#endif
+
+#if DEBUG
+@Test func debugOnly() {}
+#else
+@Test func releaseOnly() {}
+#endif
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(PlatformTests) SWIFT_TEST_CLASS=PlatformTests",
+            "swift-xctest-func(testPlatform) SWIFT_TEST_CLASS=PlatformTests SWIFT_TEST_FUNC=testPlatform",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=debugOnly",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=releaseOnly",
+        ]
+    );
+}
+
+#[test]
+fn test_parameterized_tests() {
+    let source = r#"
+@Test(arguments: ["a", "b"])
+func check(_ value: String) {}
+
+@Test(arguments: [1, 2], ["x", "y"])
+func pair(_ number: Int, _ name: String) {}
+
+func helper(_ value: String) {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=check",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=pair",
+        ]
+    );
+}
+
+#[test]
+fn test_suite_attribute_order() {
+    let source = r#"
+@MainActor @Suite struct Before {}
+@Suite @MainActor struct After {}
+@available(macOS 14, *) @Suite struct Available {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Before",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=After",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Available",
+        ]
+    );
+}
+
+#[test]
+fn test_member_tests_without_suite_attribute() {
+    let source = r#"
+struct Implicit {
+    @Test func foo() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-testing-member-func(Test) SWIFT_TEST_CLASS=Implicit SWIFT_TEST_FUNC=foo"]
+    );
+}
+
+#[test]
+fn test_suites_with_isolation_modifiers() {
+    let source = r#"
+@Suite nonisolated final class Nonisolated {}
+@Suite distributed actor Distributed {}
+@Suite public final class Public {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Nonisolated",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Distributed",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Public",
+        ]
+    );
+}
+
+#[test]
+fn test_multiline_test_attribute() {
+    let source = r#"
+@Test(
+  "long name",
+  .tags(.slow),
+  arguments: [1, 2, 3]
+)
+func foo(_ n: Int) {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-testing-bare-func(Test) SWIFT_TEST_FUNC=foo"]
+    );
+}
+
+#[test]
+fn test_main_actor_suite() {
+    let source = r#"
+@MainActor @Suite struct S {
+    @Test func foo() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=S",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=S SWIFT_TEST_FUNC=foo",
+        ]
+    );
+}
+
+#[test]
+fn test_quick_examples() {
+    let source = r#"
+final class ParserSpec: QuickSpec {
+    override class func spec() {
+        it("parses numbers") {}
+        it("parses strings") {}
+    }
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-test-quick-spec(ParserSpec) SWIFT_TEST_CLASS=ParserSpec",
+            "swift-quick-example(it) SWIFT_QUICK_EXAMPLE=parses numbers SWIFT_TEST_CLASS=ParserSpec",
+            "swift-quick-example(it) SWIFT_QUICK_EXAMPLE=parses strings SWIFT_TEST_CLASS=ParserSpec",
+        ]
+    );
+}
+
+#[test]
+fn test_nested_quick_example_groups() {
+    let source = r#"
+final class ParserSpec: AsyncSpec {
+    override class func spec() {
+        describe("a parser") {
+            context("with input") {
+                it("parses") {}
+            }
+        }
+    }
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-test-quick-spec(ParserSpec) SWIFT_TEST_CLASS=ParserSpec",
+            "swift-quick-example(describe) SWIFT_QUICK_EXAMPLE=a parser SWIFT_TEST_CLASS=ParserSpec",
+            "swift-quick-example(context) SWIFT_QUICK_EXAMPLE=with input SWIFT_TEST_CLASS=ParserSpec",
+            "swift-quick-example(it) SWIFT_QUICK_EXAMPLE=parses SWIFT_TEST_CLASS=ParserSpec",
+        ]
+    );
+}
+
+#[test]
+fn test_quick_style_calls_outside_a_spec() {
+    let source = r#"
+final class Helpers {
+    func run() {
+        describe("not a spec") {}
+    }
+}
+
+func it(_ label: String, _ body: () -> Void) {}
+"#;
+
+    assert!(summary(source).is_empty());
+}
+
+#[test]
+fn test_xctest_activities() {
+    let source = r#"
+final class CheckoutTests: XCTestCase {
+    func testPurchase() {
+        XCTContext.runActivity(named: "Add to cart") { _ in }
+    }
+
+    func helper() {
+        XCTContext.runActivity(named: "Not a test") { _ in }
+    }
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-xctest-class(CheckoutTests) SWIFT_TEST_CLASS=CheckoutTests",
+            "swift-xctest-func(testPurchase) SWIFT_TEST_CLASS=CheckoutTests SWIFT_TEST_FUNC=testPurchase",
+            "swift-xctest-activity(runActivity) SWIFT_TEST_CLASS=CheckoutTests SWIFT_TEST_FUNC=testPurchase SWIFT_XCTEST_ACTIVITY=Add to cart",
+        ]
+    );
+}
+
+#[test]
+fn test_enum_suite() {
+    let source = r#"
+@Suite enum NS {
+    @Test func t() {}
+}
+
+enum Plain {
+    case a
+    func helper() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=NS",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=NS SWIFT_TEST_FUNC=t",
+        ]
+    );
+}
+
+#[test]
+fn test_annotated_xctest_extension() {
+    let source = r#"
+// @XCTestClass
+extension MyTests {
+    func testA() {}
+    func helper() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-xctest-func(testA) SWIFT_TEST_CLASS=MyTests SWIFT_TEST_FUNC=testA"]
+    );
+}
+
+#[test]
+fn test_isolated_suite_extension() {
+    let source = r#"
+@MainActor extension MySuite {
+    @Test func f() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-testing-member-func(Test) SWIFT_TEST_CLASS=MySuite SWIFT_TEST_FUNC=f"]
+    );
+}
+
+#[test]
+fn test_suite_extensions() {
+    let source = r#"
+@Suite struct Parsing {}
+
+extension Parsing {
+    @Test func numbers() {}
+}
+
+struct Helpers {}
+
+extension Helpers {
+    @Test func strings() {}
+}
+
+extension Outer.Inner {
+    @Test func nested() {}
+}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Parsing",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Parsing SWIFT_TEST_FUNC=numbers",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Helpers SWIFT_TEST_FUNC=strings",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Inner SWIFT_TEST_FUNC=nested",
+        ]
+    );
+}
+
+#[test]
+fn test_available_attribute_before_test() {
+    let source = r#"
+@available(macOS 14, *) @Test func f() throws {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-testing-bare-func(Test) SWIFT_TEST_FUNC=f"]
+    );
+}
+
+#[test]
+fn test_suite_behind_can_import_testing() {
+    let source = r#"
+#if canImport(Testing)
+import Testing
+
+@Suite struct Parsing {
+    @Test func numbers() {}
+}
+#endif
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-test-all(Testing)",
+            "swift-testing-suite(Suite) SWIFT_TEST_CLASS=Parsing",
+            "swift-testing-member-func(Test) SWIFT_TEST_CLASS=Parsing SWIFT_TEST_FUNC=numbers",
+        ]
+    );
+}
+
+#[test]
+fn test_attributes_and_modifiers_around_test() {
+    let source = r#"
+@MainActor @Test func isolated() {}
+
+@available(macOS 14, *) @Test func available() {}
+
+private @Test func hidden() {}
+"#;
+
+    assert_eq!(
+        summary(source),
+        [
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=isolated",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=available",
+            "swift-testing-bare-func(Test) SWIFT_TEST_FUNC=hidden",
+        ]
+    );
+}
+
+#[test]
+fn test_whole_target_runnable_on_qualified_imports() {
+    let source = r#"
+import struct Testing.Test
+@testable import XCTest
+import MyKit.Testing
+"#;
+
+    assert_eq!(
+        summary(source),
+        ["swift-test-all(Testing)", "swift-test-all(XCTest)"]
+    );
+}
+
+/// Guards against patterns whose matching cost grows faster than the file.
+///
+/// The generated file has 100 `@Suite` structs of 10 `@Test` functions each,
+/// which give 1 whole-target runnable for `import Testing`, 100 suite
+/// runnables and 1,000 member-func runnables: 1,101 in total. The time budget
+/// is deliberately loose and covers compiling the query as well, so it only
+/// trips on a regression in complexity, not on a slow machine.
+#[test]
+fn test_large_file() {
+    const SUITES: usize = 100;
+    const TESTS_PER_SUITE: usize = 10;
+
+    let mut source = String::from("import Testing\n");
+    for suite in 0..SUITES {
+        source.push_str(&format!("\n@Suite struct Suite{suite} {{\n"));
+        for test in 0..TESTS_PER_SUITE {
+            source.push_str(&format!("    @Test func test{test}() {{}}\n"));
+        }
+        source.push_str("}\n");
+    }
+
+    let start = Instant::now();
+    let runnables = runnables(&source);
+    let elapsed = start.elapsed();
+
+    let count = |tag: &str| {
+        runnables
+            .iter()
+            .filter(|runnable| runnable.tag == tag)
+            .count()
+    };
+    assert_eq!(count("swift-test-all"), 1);
+    assert_eq!(count("swift-testing-suite"), SUITES);
+    assert_eq!(count("swift-testing-member-func"), SUITES * TESTS_PER_SUITE);
+    assert_eq!(runnables.len(), 1 + SUITES + SUITES * TESTS_PER_SUITE);
+    assert!(
+        elapsed < Duration::from_secs(10),
+        "matching took {elapsed:?}"
+    );
+}
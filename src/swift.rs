@@ -1,5 +1,17 @@
+#[cfg(test)]
+mod highlights_test;
+mod language_server;
+#[cfg(test)]
+mod outline_test;
+#[cfg(test)]
+mod query_test;
+#[cfg(test)]
+mod runnables_test;
+
 use zed_extension_api::{self as zed, Result};
 
+use crate::language_server::SourceKitLsp;
+
 struct SwiftExtension {}
 
 impl zed::Extension for SwiftExtension {
@@ -9,14 +21,53 @@ impl zed::Extension for SwiftExtension {
 
     fn language_server_command(
         &mut self,
-        _server_id: &zed::LanguageServerId,
-        _worktree: &zed::Worktree,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
-        Ok(zed::Command {
-            command: "/usr/bin/xcrun".into(),
-            args: vec!["sourcekit-lsp".into()],
-            env: Default::default(),
-        })
+        match language_server_id.as_ref() {
+            SourceKitLsp::LANGUAGE_SERVER_ID => {
+                let binary = SourceKitLsp::language_server_binary(worktree)?;
+                Ok(zed::Command {
+                    command: binary.path,
+                    args: binary.args,
+                    env: binary.env,
+                })
+            }
+            language_server_id => Err(format!("unknown language server: {language_server_id}")),
+        }
+    }
+
+    fn language_server_initialization_options(
+        &mut self,
+        language_server_id: &zed::LanguageServerId,
+        worktree: &zed::Worktree,
+    ) -> Result<Option<zed::serde_json::Value>> {
+        match language_server_id.as_ref() {
+            SourceKitLsp::LANGUAGE_SERVER_ID => SourceKitLsp::initialization_options(worktree),
+            _ => Ok(None),
+        }
+    }
+
+    fn label_for_completion(
+        &self,
+        language_server_id: &zed::LanguageServerId,
+        completion: zed::lsp::Completion,
+    ) -> Option<zed::CodeLabel> {
+        match language_server_id.as_ref() {
+            SourceKitLsp::LANGUAGE_SERVER_ID => SourceKitLsp::label_for_completion(completion),
+            _ => None,
+        }
+    }
+
+    fn label_for_symbol(
+        &self,
+        language_server_id: &zed::LanguageServerId,
+        symbol: zed::lsp::Symbol,
+    ) -> Option<zed::CodeLabel> {
+        match language_server_id.as_ref() {
+            SourceKitLsp::LANGUAGE_SERVER_ID => SourceKitLsp::label_for_symbol(symbol),
+            _ => None,
+        }
     }
 }
 